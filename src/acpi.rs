@@ -0,0 +1,194 @@
+//! Helpers for locating the ACPI tables referenced by a [`BootloaderResponse`].
+
+use crate::{address::PhysAddr, BootloaderResponse};
+
+/// The size, in bytes, of an ACPI 1.0 RSDP.
+const RSDP_V1_LENGTH: usize = 20;
+
+/// The offset of the revision field in the RSDP.
+const RSDP_REVISION_OFFSET: usize = 15;
+
+/// The offset of the length field in an ACPI 2.0+ RSDP.
+const RSDP_LENGTH_OFFSET: usize = 20;
+
+/// The offset of the 32-bit physical address of the RSDT in the RSDP.
+const RSDP_RSDT_OFFSET: usize = 16;
+
+/// The offset of the 64-bit physical address of the XSDT in an ACPI 2.0+ RSDP.
+const RSDP_XSDT_OFFSET: usize = 24;
+
+/// The offset of the length field in the header of an ACPI system description table.
+const SDT_LENGTH_OFFSET: usize = 4;
+
+/// The size, in bytes, of the header of an ACPI system description table.
+const SDT_HEADER_LENGTH: usize = 36;
+
+impl BootloaderResponse {
+    /// Returns the bytes of the ACPI RSDP.
+    ///
+    /// For revision 0, the slice is 20 bytes long. For revision 2 and above, the slice is as
+    /// long as the RSDP's length field. Returns [`None`] if
+    /// [`BootloaderResponse::rsdp_table_ptr`] is NULL.
+    ///
+    /// # Safety
+    /// If [`BootloaderResponse::rsdp_table_ptr`] is not NULL, it must point to a readable RSDP
+    /// that remains valid and unmodified for the lifetime of `self`.
+    pub unsafe fn rsdp_bytes(&self) -> Option<&[u8]> {
+        let rsdp = self.rsdp_table_ptr.cast::<u8>();
+        if rsdp.is_null() {
+            return None;
+        }
+
+        // SAFETY: the caller guarantees that the RSDP is readable.
+        let revision = unsafe { rsdp.add(RSDP_REVISION_OFFSET).read() };
+        let length = if revision < 2 {
+            RSDP_V1_LENGTH
+        } else {
+            // SAFETY: the caller guarantees that the RSDP is readable, and an ACPI 2.0+ RSDP
+            // contains a length field.
+            unsafe { rsdp.add(RSDP_LENGTH_OFFSET).cast::<u32>().read_unaligned() as usize }
+        };
+
+        // SAFETY: the caller guarantees that the RSDP is valid for the lifetime of `self`.
+        Some(unsafe { core::slice::from_raw_parts(rsdp, length) })
+    }
+}
+
+impl BootloaderResponse {
+    /// Returns an iterator over the physical addresses of the ACPI tables listed by the XSDT,
+    /// or by the RSDT if the RSDP does not provide an XSDT.
+    ///
+    /// The XSDT or RSDT is read through [`BootloaderResponse::direct_map`]. Returns [`None`]
+    /// if [`BootloaderResponse::rsdp_table_ptr`] is NULL or the RSDP lists no XSDT or RSDT.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::rsdp_bytes()`] apply, and the XSDT or
+    /// RSDT must be mapped readable through [`BootloaderResponse::direct_map`] and remain
+    /// valid and unmodified for the lifetime of `self`.
+    pub unsafe fn acpi_tables(&self) -> Option<AcpiTableIter<'_>> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::rsdp_bytes()`.
+        let rsdp = unsafe { self.rsdp_bytes() }?;
+        let read = |offset: usize, size: usize| {
+            let mut bytes = [0; 8];
+            bytes[..size].copy_from_slice(rsdp.get(offset..offset + size)?);
+            Some(u64::from_le_bytes(bytes))
+        };
+
+        let (sdt, entry_size) = match read(RSDP_XSDT_OFFSET, 8) {
+            Some(xsdt) if xsdt != 0 => (xsdt, 8),
+            _ => (read(RSDP_RSDT_OFFSET, 4).filter(|&rsdt| rsdt != 0)?, 4),
+        };
+
+        let sdt = self.direct_map().phys_to_virt(sdt) as *const u8;
+        // SAFETY: the caller guarantees that the XSDT or RSDT is readable, and every system
+        // description table starts with a header containing a length field.
+        let length = unsafe { sdt.add(SDT_LENGTH_OFFSET).cast::<u32>().read_unaligned() };
+        let entries_length = (length as usize).saturating_sub(SDT_HEADER_LENGTH);
+
+        // SAFETY: the caller guarantees that the XSDT or RSDT is valid for the lifetime of
+        // `self`, and its length field covers its header and entries.
+        let entries = unsafe {
+            core::slice::from_raw_parts(sdt.wrapping_add(SDT_HEADER_LENGTH), entries_length)
+        };
+
+        Some(AcpiTableIter {
+            entries,
+            entry_size,
+        })
+    }
+}
+
+/// An iterator over the physical addresses of the ACPI tables listed by the XSDT or RSDT.
+#[derive(Clone, Debug)]
+pub struct AcpiTableIter<'a> {
+    entries: &'a [u8],
+    entry_size: usize,
+}
+
+impl Iterator for AcpiTableIter<'_> {
+    type Item = PhysAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.entries.len() < self.entry_size {
+            return None;
+        }
+
+        let (entry, rest) = self.entries.split_at(self.entry_size);
+        self.entries = rest;
+
+        let mut bytes = [0; 8];
+        bytes[..self.entry_size].copy_from_slice(entry);
+        Some(PhysAddr::new(u64::from_le_bytes(bytes)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.entries.len() / self.entry_size;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for AcpiTableIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::response;
+
+    /// Returns an RSDP of `revision` listing the RSDT at `rsdt` and, for revision 2 and above,
+    /// the XSDT at `xsdt`.
+    fn rsdp(revision: u8, rsdt: u32, xsdt: u64) -> [u8; 36] {
+        let mut rsdp = [0; 36];
+        rsdp[..8].copy_from_slice(b"RSD PTR ");
+        rsdp[15] = revision;
+        rsdp[16..20].copy_from_slice(&rsdt.to_le_bytes());
+        if revision >= 2 {
+            rsdp[20..24].copy_from_slice(&36u32.to_le_bytes());
+            rsdp[24..32].copy_from_slice(&xsdt.to_le_bytes());
+        }
+        rsdp
+    }
+
+    #[test]
+    fn rsdp_lengths() {
+        let (v1, v2) = (rsdp(0, 0x1000, 0), rsdp(2, 0x1000, 0x2000));
+        let mut response = response();
+
+        response.rsdp_table_ptr = v1.as_ptr().cast();
+        // SAFETY: the RSDP points to `v1`, which outlives `response`.
+        assert_eq!(unsafe { response.rsdp_bytes() }, Some(&v1[..20]));
+
+        response.rsdp_table_ptr = v2.as_ptr().cast();
+        // SAFETY: the RSDP points to `v2`, which outlives `response`.
+        assert_eq!(unsafe { response.rsdp_bytes() }, Some(&v2[..]));
+
+        response.rsdp_table_ptr = core::ptr::null();
+        // SAFETY: the RSDP is NULL.
+        assert_eq!(unsafe { response.rsdp_bytes() }, None);
+    }
+
+    #[test]
+    fn xsdt_lists_two_tables() {
+        const XSDT_PHYSICAL: u64 = 0x2000;
+
+        let mut xsdt = [0u8; SDT_HEADER_LENGTH + 16];
+        xsdt[..4].copy_from_slice(b"XSDT");
+        xsdt[SDT_LENGTH_OFFSET..][..4]
+            .copy_from_slice(&(SDT_HEADER_LENGTH as u32 + 16).to_le_bytes());
+        xsdt[SDT_HEADER_LENGTH..][..8].copy_from_slice(&0x1_0000_u64.to_le_bytes());
+        xsdt[SDT_HEADER_LENGTH + 8..].copy_from_slice(&0x2_0000_u64.to_le_bytes());
+        let rsdp = rsdp(2, 0, XSDT_PHYSICAL);
+
+        let mut response = response();
+        response.rsdp_table_ptr = rsdp.as_ptr().cast();
+        response.direct_map = (xsdt.as_ptr() as usize).wrapping_sub(XSDT_PHYSICAL as usize);
+
+        // SAFETY: the RSDP points to `rsdp`, and the XSDT it lists is `xsdt` through the direct
+        // map, both of which outlive `response`.
+        let tables = unsafe { response.acpi_tables() }.unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(
+            tables.collect::<std::vec::Vec<_>>(),
+            [PhysAddr::new(0x1_0000), PhysAddr::new(0x2_0000)]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Typed wrappers that keep the different address spaces described by a
+//! [`BootloaderResponse`] apart.
+//!
+//! [`BootloaderResponse`]: crate::BootloaderResponse
+
+/// The offset at which physical memory is mapped into the higher half of the virtual address
+/// space.
+///
+/// This is an offset rather than an address; use [`DirectMapOffset::phys_to_virt`] and
+/// [`DirectMapOffset::virt_to_phys`] to translate between the two address spaces.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DirectMapOffset(pub usize);
+
+impl DirectMapOffset {
+    /// Returns the virtual address at which the physical address `phys` is mapped.
+    pub const fn phys_to_virt(self, phys: u64) -> usize {
+        (phys as usize).wrapping_add(self.0)
+    }
+
+    /// Returns the physical address mapped at the virtual address `virt`.
+    ///
+    /// `virt` must lie within the direct map for the result to be meaningful.
+    pub const fn virt_to_phys(self, virt: usize) -> u64 {
+        virt.wrapping_sub(self.0) as u64
+    }
+}
+
+/// A physical address.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PhysAddr(u64);
+
+impl PhysAddr {
+    /// Creates a new [`PhysAddr`].
+    ///
+    /// This is the unchecked escape hatch: nothing ties `address` to any address space
+    /// described by the response. Prefer a checked conversion such as
+    /// [`BootloaderResponse::kernel_virt_to_phys`] where one applies.
+    ///
+    /// [`BootloaderResponse::kernel_virt_to_phys`]: crate::BootloaderResponse::kernel_virt_to_phys
+    pub const fn new(address: u64) -> Self {
+        Self(address)
+    }
+
+    /// Returns the raw value of this [`PhysAddr`].
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the [`VirtAddr`] at which this [`PhysAddr`] is mapped in the direct map.
+    pub const fn to_virt(self, direct_map: DirectMapOffset) -> VirtAddr {
+        VirtAddr(direct_map.phys_to_virt(self.0))
+    }
+}
+
+/// A virtual address.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VirtAddr(usize);
+
+impl VirtAddr {
+    /// Creates a new [`VirtAddr`].
+    ///
+    /// This is the unchecked escape hatch: nothing ties `address` to any address space
+    /// described by the response. Prefer a checked conversion such as
+    /// [`BootloaderResponse::kernel_phys_to_virt`] where one applies.
+    ///
+    /// [`BootloaderResponse::kernel_phys_to_virt`]: crate::BootloaderResponse::kernel_phys_to_virt
+    pub const fn new(address: usize) -> Self {
+        Self(address)
+    }
+
+    /// Creates a new [`VirtAddr`] from the address of `ptr`.
+    pub fn from_ptr<T>(ptr: *const T) -> Self {
+        Self(ptr as usize)
+    }
+
+    /// Returns the raw value of this [`VirtAddr`].
+    pub const fn as_usize(self) -> usize {
+        self.0
+    }
+
+    /// Returns the [`PhysAddr`] mapped at this [`VirtAddr`] in the direct map.
+    ///
+    /// This [`VirtAddr`] must lie within the direct map for the result to be meaningful.
+    pub const fn to_phys(self, direct_map: DirectMapOffset) -> PhysAddr {
+        PhysAddr(direct_map.virt_to_phys(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn direct_map_round_trip() {
+        let direct_map = DirectMapOffset(0xffff_8000_0000_0000);
+
+        assert_eq!(direct_map.phys_to_virt(0x1000), 0xffff_8000_0000_1000);
+        assert_eq!(direct_map.virt_to_phys(0xffff_8000_0000_1000), 0x1000);
+        assert_eq!(
+            direct_map.virt_to_phys(direct_map.phys_to_virt(0x1234_5678)),
+            0x1234_5678
+        );
+
+        let phys = PhysAddr::new(0xdead_b000);
+        assert_eq!(phys.to_virt(direct_map).to_phys(direct_map), phys);
+        assert_eq!(
+            phys.to_virt(direct_map),
+            VirtAddr::new(0xffff_8000_dead_b000)
+        );
+    }
+}
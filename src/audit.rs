@@ -0,0 +1,418 @@
+//! Validation of the invariants of a [`BootloaderResponse`].
+
+use core::fmt;
+
+use crate::{
+    fnv1a_bytes,
+    memory_map::{self, MemoryMapError},
+    module::{self, ModuleError},
+    BootloaderResponse, MemoryMapEntry, MemoryMapEntryKind, FNV_OFFSET_BASIS, RESPONSE_SIGNATURE,
+};
+
+/// The results of running every available validator over a [`BootloaderResponse`].
+///
+/// Unlike the individual validators, an audit does not stop at the first problem, which allows
+/// a bootloader to report everything wrong with a response before aborting.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct ResponseAudit {
+    /// Whether [`BootloaderResponse::signature`] is [`RESPONSE_SIGNATURE`].
+    pub signature: bool,
+    /// Whether [`BootloaderResponse::memory_map_entries`] is non-NULL whenever
+    /// [`BootloaderResponse::memory_map_entry_count`] is non-zero.
+    ///
+    /// If this is `false`, the memory map checks are skipped and reported as passing.
+    pub memory_map_array: bool,
+    /// The result of [`memory_map::validate_alignment`].
+    pub memory_map_alignment: Result<(), MemoryMapError>,
+    /// The result of [`memory_map::validate_non_empty`].
+    pub memory_map_non_empty: Result<(), MemoryMapError>,
+    /// The result of [`memory_map::validate_sorted`].
+    pub memory_map_sorted: Result<(), MemoryMapError>,
+    /// The result of [`memory_map::validate_non_overlapping`].
+    pub memory_map_non_overlapping: Result<(), MemoryMapError>,
+    /// Whether [`BootloaderResponse::memory_map_checksum`] is the [`memory_map::checksum`] of
+    /// the memory map.
+    ///
+    /// This is reported as passing if the response predates
+    /// [`BootloaderResponse::memory_map_checksum`].
+    pub memory_map_checksum: bool,
+    /// Whether [`BootloaderResponse::module_entries`] is non-NULL whenever
+    /// [`BootloaderResponse::module_entry_count`] is non-zero.
+    ///
+    /// If this is `false`, the module checks are skipped and reported as passing.
+    pub module_array: bool,
+    /// The result of [`module::validate_module_pointers`].
+    pub modules: Result<(), ModuleError>,
+}
+
+impl ResponseAudit {
+    /// Returns `true` if every check in the audit passed.
+    pub fn passed(&self) -> bool {
+        self.signature
+            && self.memory_map_array
+            && self.memory_map_alignment.is_ok()
+            && self.memory_map_non_empty.is_ok()
+            && self.memory_map_sorted.is_ok()
+            && self.memory_map_non_overlapping.is_ok()
+            && self.memory_map_checksum
+            && self.module_array
+            && self.modules.is_ok()
+    }
+}
+
+impl fmt::Display for ResponseAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_check<E: fmt::Display>(
+            f: &mut fmt::Formatter<'_>,
+            name: &str,
+            result: &Result<(), E>,
+        ) -> fmt::Result {
+            match result {
+                Ok(()) => writeln!(f, "{name}: ok"),
+                Err(error) => writeln!(f, "{name}: {error}"),
+            }
+        }
+
+        let array = |present: bool| {
+            if present {
+                Ok(())
+            } else {
+                Err("NULL array with non-zero count")
+            }
+        };
+
+        let signature = if self.signature {
+            Ok(())
+        } else {
+            Err("invalid response signature")
+        };
+
+        let checksum = if self.memory_map_checksum {
+            Ok(())
+        } else {
+            Err("memory map does not match its checksum")
+        };
+
+        write_check(f, "signature", &signature)?;
+        write_check(f, "memory map array", &array(self.memory_map_array))?;
+        write_check(f, "memory map alignment", &self.memory_map_alignment)?;
+        write_check(f, "memory map sizes", &self.memory_map_non_empty)?;
+        write_check(f, "memory map ordering", &self.memory_map_sorted)?;
+        write_check(f, "memory map overlap", &self.memory_map_non_overlapping)?;
+        write_check(f, "memory map checksum", &checksum)?;
+        write_check(f, "module array", &array(self.module_array))?;
+        write_check(f, "modules", &self.modules)
+    }
+}
+
+impl BootloaderResponse {
+    /// Runs every available validator over this [`BootloaderResponse`], collecting the results
+    /// into a [`ResponseAudit`].
+    ///
+    /// # Safety
+    /// Every non-NULL array pointer in `self` must be valid for its corresponding count.
+    pub unsafe fn audit(&self) -> ResponseAudit {
+        let memory_map_array =
+            !self.memory_map_entries.is_null() || self.memory_map_entry_count == 0;
+        let module_array = !self.module_entries.is_null() || self.module_entry_count == 0;
+
+        let memory_map = if memory_map_array {
+            // SAFETY: the caller guarantees that the memory map array is valid.
+            unsafe { self.memory_map() }
+        } else {
+            &[]
+        };
+        let modules = if module_array {
+            // SAFETY: the caller guarantees that the module array is valid.
+            unsafe { self.modules() }
+        } else {
+            &[]
+        };
+
+        ResponseAudit {
+            signature: self.signature == RESPONSE_SIGNATURE,
+            memory_map_array,
+            memory_map_alignment: memory_map::validate_alignment(memory_map),
+            memory_map_non_empty: memory_map::validate_non_empty(memory_map),
+            memory_map_sorted: memory_map::validate_sorted(memory_map),
+            memory_map_non_overlapping: memory_map::validate_non_overlapping(memory_map),
+            memory_map_checksum: self
+                .memory_map_checksum()
+                .is_none_or(|checksum| checksum == memory_map::checksum(memory_map)),
+            module_array,
+            modules: module::validate_module_pointers(modules),
+        }
+    }
+}
+
+/// Various errors that can occur when checking that the memory map covers the kernel and its
+/// modules.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CoverageError {
+    /// The memory map contains no [`MemoryMapEntryKind::KERNEL`] region.
+    MissingKernel,
+    /// The module at `index` does not lie within a single [`MemoryMapEntryKind::MODULE`]
+    /// region.
+    UncoveredModule {
+        /// The index of the offending module.
+        index: usize,
+    },
+    /// The [`MemoryMapEntryKind::USABLE`] region at `index` overlaps the physical range of
+    /// the kernel image.
+    UsableKernel {
+        /// The index of the offending region.
+        index: usize,
+    },
+    /// The response does not report the physical range of the kernel image, see
+    /// [`BootloaderResponse::kernel_physical_range()`].
+    UnknownKernelLocation,
+}
+
+impl fmt::Display for CoverageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingKernel => f.write_str("memory map contains no kernel region"),
+            Self::UncoveredModule { index } => {
+                write!(f, "module {index} does not lie within a module region")
+            }
+            Self::UsableKernel { index } => {
+                write!(f, "usable region {index} overlaps the kernel")
+            }
+            Self::UnknownKernelLocation => f.write_str("kernel physical range is not reported"),
+        }
+    }
+}
+
+impl BootloaderResponse {
+    /// Checks that the memory map contains a [`MemoryMapEntryKind::KERNEL`] region and that
+    /// every module lies within a [`MemoryMapEntryKind::MODULE`] region.
+    ///
+    /// Module addresses are translated to physical addresses through
+    /// [`BootloaderResponse::direct_map`].
+    ///
+    /// # Errors
+    /// Returns the [`CoverageError`] describing the first uncovered region.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::memory_map()`] and
+    /// [`BootloaderResponse::modules()`] apply.
+    pub unsafe fn check_region_coverage(&self) -> Result<(), CoverageError> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+        let memory_map = unsafe { self.memory_map() };
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let modules = unsafe { self.modules() };
+
+        if !memory_map
+            .iter()
+            .any(|entry| entry.kind == MemoryMapEntryKind::KERNEL)
+        {
+            return Err(CoverageError::MissingKernel);
+        }
+
+        let direct_map = self.direct_map();
+        for (index, module) in modules.iter().enumerate() {
+            let base = direct_map.virt_to_phys(module.address as usize);
+            let covered = memory_map.iter().any(|entry| {
+                entry.kind == MemoryMapEntryKind::MODULE
+                    && base >= entry.base
+                    && (base - entry.base).saturating_add(module.size as u64) <= entry.size
+            });
+            if !covered {
+                return Err(CoverageError::UncoveredModule { index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that no [`MemoryMapEntryKind::USABLE`] region overlaps the physical range of the
+    /// kernel image, which would let the kernel allocate over itself.
+    ///
+    /// # Errors
+    /// Returns [`CoverageError::UnknownKernelLocation`] if
+    /// [`BootloaderResponse::kernel_physical_range()`] is [`None`], or
+    /// [`CoverageError::UsableKernel`] for the first usable region that overlaps the kernel.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::memory_map()`] apply.
+    pub unsafe fn check_kernel_not_usable(&self) -> Result<(), CoverageError> {
+        let kernel = self
+            .kernel_physical_range()
+            .ok_or(CoverageError::UnknownKernelLocation)?;
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+        let memory_map = unsafe { self.memory_map() };
+
+        match memory_map.iter().position(|entry| {
+            entry.kind == MemoryMapEntryKind::USABLE
+                && entry.base < kernel.end
+                && kernel.start < entry.end_addr().as_u64()
+        }) {
+            Some(index) => Err(CoverageError::UsableKernel { index }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl BootloaderResponse {
+    /// Returns the 64-bit FNV-1a hash of the bytes of the region described by `entry`, read
+    /// through [`BootloaderResponse::direct_map`].
+    ///
+    /// This reads the entire region, and so is intended as a diagnostic for detecting
+    /// corruption of reserved memory by DMA or firmware, by comparing checksums taken at
+    /// different times.
+    ///
+    /// Returns [`None`] if the size of the region does not fit in a `usize`, as can happen on
+    /// 32-bit targets, since such a region cannot be read in its entirety.
+    ///
+    /// # Safety
+    /// Every byte of the region described by `entry` must be mapped readable through
+    /// [`BootloaderResponse::direct_map`].
+    pub unsafe fn region_checksum(&self, entry: &MemoryMapEntry) -> Option<u64> {
+        if entry.size == 0 {
+            return Some(FNV_OFFSET_BASIS);
+        }
+
+        let size = usize::try_from(entry.size).ok()?;
+        let base = self.direct_map().phys_to_virt(entry.base) as *const u8;
+        // SAFETY: the caller guarantees that the region is readable through the direct map.
+        let bytes = unsafe { core::slice::from_raw_parts(base, size) };
+        Some(fnv1a_bytes(FNV_OFFSET_BASIS, bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{entry, module, response, set_memory_map, set_modules};
+
+    #[test]
+    fn audit_reports_every_flaw() {
+        let mut entries = [
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x800),
+        ];
+        let mut response = response();
+        response.signature = [0; 3];
+        set_memory_map(&mut response, &mut entries);
+        response.memory_map_checksum ^= 1;
+
+        // SAFETY: the memory map points to `entries`, which outlives `response`.
+        let audit = unsafe { response.audit() };
+        assert!(!audit.passed());
+        assert!(!audit.signature);
+        assert!(!audit.memory_map_checksum);
+        assert!(audit.memory_map_alignment.is_err());
+        assert!(audit.memory_map_sorted.is_err());
+        assert!(audit.memory_map_non_overlapping.is_err());
+        assert!(audit.memory_map_non_empty.is_ok());
+        assert!(audit.modules.is_ok());
+
+        let report = std::format!("{audit}");
+        assert!(report.contains("signature: invalid response signature"));
+        assert!(report.contains("memory map sizes: ok"));
+        assert!(report.contains("memory map checksum: memory map does not match its checksum"));
+        assert_eq!(
+            report
+                .lines()
+                .filter(|line| !line.ends_with(": ok"))
+                .count(),
+            5
+        );
+
+        response.api_version = 16;
+        // SAFETY: the memory map points to `entries`, which outlives `response`.
+        assert!(unsafe { response.audit() }.memory_map_checksum);
+    }
+
+    #[test]
+    fn uncovered_module() {
+        let mut entries = [
+            entry(MemoryMapEntryKind::KERNEL, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::MODULE, 0x2000, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x4000, 0x4000),
+        ];
+        let mut modules = [
+            module("inside", 0x2000 as *const u8, 0x2000),
+            module("outside", 0x5000 as *const u8, 0x1000),
+        ];
+        let mut response = response();
+        set_memory_map(&mut response, &mut entries);
+        set_modules(&mut response, &mut modules[..1]);
+
+        // SAFETY: the arrays point to locals that outlive `response`.
+        assert_eq!(unsafe { response.check_region_coverage() }, Ok(()));
+
+        set_modules(&mut response, &mut modules);
+        // SAFETY: the arrays point to locals that outlive `response`.
+        assert_eq!(
+            unsafe { response.check_region_coverage() },
+            Err(CoverageError::UncoveredModule { index: 1 })
+        );
+
+        set_memory_map(&mut response, &mut entries[1..]);
+        // SAFETY: the arrays point to locals that outlive `response`.
+        assert_eq!(
+            unsafe { response.check_region_coverage() },
+            Err(CoverageError::MissingKernel)
+        );
+    }
+
+    #[test]
+    fn region_checksum_is_fnv1a() {
+        let bytes = *b"foobar";
+        let mut response = response();
+        response.direct_map = bytes.as_ptr() as usize;
+
+        let region = entry(MemoryMapEntryKind::RESERVED, 0, bytes.len() as u64);
+        // SAFETY: the region is `bytes`, which is mapped at the direct map.
+        assert_eq!(
+            unsafe { response.region_checksum(&region) },
+            Some(0x85944171f73967e8)
+        );
+
+        let empty = entry(MemoryMapEntryKind::RESERVED, 0, 0);
+        // SAFETY: the region is empty, so nothing is read.
+        assert_eq!(
+            unsafe { response.region_checksum(&empty) },
+            Some(FNV_OFFSET_BASIS)
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn region_checksum_of_unaddressable_region() {
+        let response = response();
+        let region = entry(MemoryMapEntryKind::RESERVED, 0, 1 << 32);
+        // SAFETY: the region does not fit in a `usize`, so nothing is read.
+        assert_eq!(unsafe { response.region_checksum(&region) }, None);
+    }
+
+    #[test]
+    fn usable_region_over_kernel_image() {
+        let mut entries = [
+            entry(MemoryMapEntryKind::KERNEL, 0x10_0000, 0x4000),
+            entry(MemoryMapEntryKind::USABLE, 0x10_4000, 0x4000),
+        ];
+        let mut response = response();
+        set_memory_map(&mut response, &mut entries);
+        response.kernel_physical_address = 0x10_0000;
+        response.kernel_size = 0x4000;
+
+        // SAFETY: the memory map points to a local that outlives `response`.
+        assert_eq!(unsafe { response.check_kernel_not_usable() }, Ok(()));
+
+        response.kernel_size = 0x5000;
+        // SAFETY: the memory map points to a local that outlives `response`.
+        assert_eq!(
+            unsafe { response.check_kernel_not_usable() },
+            Err(CoverageError::UsableKernel { index: 1 })
+        );
+
+        response.api_version = 14;
+        // SAFETY: the memory map points to a local that outlives `response`.
+        assert_eq!(
+            unsafe { response.check_kernel_not_usable() },
+            Err(CoverageError::UnknownKernelLocation)
+        );
+    }
+}
@@ -0,0 +1,561 @@
+//! Construction of a [`BootloaderResponse`] by the bootloader.
+
+use core::{ffi::c_void, marker::PhantomData};
+
+use crate::{
+    address::{DirectMapOffset, VirtAddr},
+    memory_map::{self, MemoryMapError},
+    module::{self, ModuleError},
+    uefi::UefiDescriptorVersion,
+    BootloaderResponse, ExtensionHeader, Framebuffer, MemoryMapEntry, ModuleEntry, API_VERSION,
+    CAP_FRAMEBUFFER, CAP_RSDP, CAP_SMBIOS, CAP_UEFI_RUNTIME, PROTOCOL_REVISION, RESPONSE_SIGNATURE,
+};
+
+/// Various errors that can occur when building a [`BootloaderResponse`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BuildError {
+    /// The pointer of the array or string `field` is NULL while its length is non-zero, or
+    /// non-NULL while its length is 0.
+    InconsistentLength {
+        /// The name of the offending field.
+        field: &'static str,
+    },
+    /// The memory map violates one of its invariants.
+    MemoryMap(MemoryMapError),
+    /// One of the modules is inconsistent.
+    Module(ModuleError),
+}
+
+impl core::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InconsistentLength { field } => {
+                write!(f, "{field} has a pointer and length that disagree")
+            }
+            Self::MemoryMap(error) => write!(f, "invalid memory map: {error}"),
+            Self::Module(error) => write!(f, "invalid module: {error}"),
+        }
+    }
+}
+
+/// A builder of a [`BootloaderResponse`] whose arrays and strings borrow caller-owned
+/// memory for `'a`.
+///
+/// Empty arrays and strings are stored as a NULL pointer with a length of 0. Each method that
+/// fills an optional field also sets or clears the corresponding `CAP_*` bit of
+/// [`BootloaderResponse::capabilities`]. The built
+/// [`BootloaderResponse`] holds raw pointers, so the bootloader must keep the borrowed memory
+/// alive until the kernel no longer needs it.
+#[derive(Debug)]
+pub struct BootloaderResponseBuilder<'a> {
+    response: BootloaderResponse,
+    /// The last block added by [`BootloaderResponseBuilder::add_extension`], or NULL.
+    last_extension: *mut ExtensionHeader,
+    borrows: PhantomData<&'a mut ()>,
+}
+
+/// Returns the pointer and length of `slice`, with a NULL pointer if it is empty.
+fn slice_parts<T>(slice: &mut [T]) -> (*mut T, usize) {
+    if slice.is_empty() {
+        return (core::ptr::null_mut(), 0);
+    }
+
+    (slice.as_mut_ptr(), slice.len())
+}
+
+/// Returns the pointer and length of `string`, with a NULL pointer if it is empty.
+fn str_parts(string: &str) -> (*const u8, usize) {
+    if string.is_empty() {
+        return (core::ptr::null(), 0);
+    }
+
+    (string.as_ptr(), string.len())
+}
+
+impl<'a> BootloaderResponseBuilder<'a> {
+    /// Creates a new [`BootloaderResponseBuilder`] for the current [`API_VERSION`] and
+    /// [`PROTOCOL_REVISION`], with every other field zeroed.
+    pub const fn new() -> Self {
+        // SAFETY: every field of `BootloaderResponse` is an integer or a raw pointer, for which
+        // all zeroes is a valid value.
+        let mut response: BootloaderResponse = unsafe { core::mem::zeroed() };
+        response.signature = RESPONSE_SIGNATURE;
+        response.api_version = API_VERSION;
+        response.protocol_revision = PROTOCOL_REVISION.as_ptr();
+        response.protocol_revision_length = PROTOCOL_REVISION.len();
+        response.memory_map_checksum = memory_map::checksum(&[]);
+
+        Self {
+            response,
+            last_extension: core::ptr::null_mut(),
+            borrows: PhantomData,
+        }
+    }
+
+    /// Sets `cap` in [`BootloaderResponse::capabilities`] if `provided`, and clears it
+    /// otherwise.
+    fn set_capability(&mut self, cap: u64, provided: bool) {
+        if provided {
+            self.response.capabilities |= cap;
+        } else {
+            self.response.capabilities &= !cap;
+        }
+    }
+
+    /// Sets [`BootloaderResponse::bootloader_name`] to `name`.
+    pub fn bootloader_name(&mut self, name: &'a str) -> &mut Self {
+        (
+            self.response.bootloader_name,
+            self.response.bootloader_name_length,
+        ) = str_parts(name);
+        self
+    }
+
+    /// Sets [`BootloaderResponse::bootloader_version`] to `version`.
+    pub fn bootloader_version(&mut self, version: &'a str) -> &mut Self {
+        (
+            self.response.bootloader_version,
+            self.response.bootloader_version_length,
+        ) = str_parts(version);
+        self
+    }
+
+    /// Sets [`BootloaderResponse::firmware_vendor`] to `vendor`.
+    pub fn firmware_vendor(&mut self, vendor: &'a str) -> &mut Self {
+        (
+            self.response.firmware_vendor,
+            self.response.firmware_vendor_length,
+        ) = str_parts(vendor);
+        self
+    }
+
+    /// Sets [`BootloaderResponse::kernel_virtual_address`] and
+    /// [`BootloaderResponse::kernel_entry`].
+    pub fn kernel(&mut self, virtual_address: VirtAddr, entry: VirtAddr) -> &mut Self {
+        self.response.kernel_virtual_address = virtual_address.as_usize() as *const c_void;
+        self.response.kernel_entry = entry.as_usize() as *const c_void;
+        self
+    }
+
+    /// Sets [`BootloaderResponse::kernel_physical_address`] and
+    /// [`BootloaderResponse::kernel_size`].
+    pub fn kernel_physical(&mut self, address: u64, size: u64) -> &mut Self {
+        self.response.kernel_physical_address = address;
+        self.response.kernel_size = size;
+        self
+    }
+
+    /// Sets [`BootloaderResponse::kaslr_slide`].
+    pub fn kaslr_slide(&mut self, slide: i64) -> &mut Self {
+        self.response.kaslr_slide = slide;
+        self
+    }
+
+    /// Sets [`BootloaderResponse::direct_map`].
+    pub fn direct_map(&mut self, direct_map: DirectMapOffset) -> &mut Self {
+        self.response.direct_map = direct_map.0;
+        self
+    }
+
+    /// Sets [`BootloaderResponse::paging_levels`] and
+    /// [`BootloaderResponse::page_table_root`].
+    pub fn paging(&mut self, levels: u8, page_table_root: u64) -> &mut Self {
+        self.response.paging_levels = levels;
+        self.response.page_table_root = page_table_root;
+        self
+    }
+
+    /// Points the memory map at `entries` and sets [`BootloaderResponse::memory_map_checksum`]
+    /// to their [`memory_map::checksum`].
+    pub fn memory_map(&mut self, entries: &'a mut [MemoryMapEntry]) -> &mut Self {
+        self.response.memory_map_checksum = memory_map::checksum(entries);
+        (
+            self.response.memory_map_entries,
+            self.response.memory_map_entry_count,
+        ) = slice_parts(entries);
+        self
+    }
+
+    /// Drains `entries` into `buf`, sorts the written entries by base address, validates them,
+    /// and points the memory map at them.
+    ///
+    /// This allows a bootloader to produce its memory map lazily without first collecting it.
+    /// The memory map is left unchanged if this fails.
+    ///
+    /// # Errors
+    /// - [`BuildError::MemoryMap`] with [`MemoryMapError::BufferTooSmall`]: `buf` cannot hold
+    ///   every entry.
+    /// - [`BuildError::MemoryMap`]: the sorted entries fail
+    ///   [`memory_map::validate_memory_map`].
+    pub fn memory_map_from_iter<I: IntoIterator<Item = MemoryMapEntry>>(
+        &mut self,
+        entries: I,
+        buf: &'a mut [MemoryMapEntry],
+    ) -> Result<(), BuildError> {
+        let mut count = 0;
+        for (index, entry) in entries.into_iter().enumerate() {
+            let slot = buf.get_mut(index).ok_or(BuildError::MemoryMap(
+                MemoryMapError::BufferTooSmall { index },
+            ))?;
+            *slot = entry;
+            count += 1;
+        }
+
+        let (written, _) = buf.split_at_mut(count);
+        written.sort_unstable_by_key(|entry| entry.base);
+        memory_map::validate_memory_map(written).map_err(BuildError::MemoryMap)?;
+        self.memory_map(written);
+        Ok(())
+    }
+
+    /// Points the modules at `modules`.
+    pub fn modules(&mut self, modules: &'a mut [ModuleEntry]) -> &mut Self {
+        (
+            self.response.module_entries,
+            self.response.module_entry_count,
+        ) = slice_parts(modules);
+        self
+    }
+
+    /// Points the framebuffers at `framebuffers`, the first of which is the primary one.
+    pub fn framebuffers(&mut self, framebuffers: &'a mut [Framebuffer]) -> &mut Self {
+        (self.response.framebuffers, self.response.framebuffer_count) = slice_parts(framebuffers);
+        self.set_capability(CAP_FRAMEBUFFER, self.response.framebuffer_count != 0);
+        self
+    }
+
+    /// Sets [`BootloaderResponse::rsdp_table_ptr`] and
+    /// [`BootloaderResponse::rsdp_physical_address`].
+    pub fn rsdp(&mut self, table: *const c_void, physical_address: u64) -> &mut Self {
+        self.response.rsdp_table_ptr = table;
+        self.response.rsdp_physical_address = physical_address;
+        self.set_capability(CAP_RSDP, !table.is_null());
+        self
+    }
+
+    /// Sets [`BootloaderResponse::sm_bios_entry_32`] and
+    /// [`BootloaderResponse::sm_bios_entry_64`].
+    pub fn sm_bios(&mut self, entry_32: *const c_void, entry_64: *const c_void) -> &mut Self {
+        self.response.sm_bios_entry_32 = entry_32;
+        self.response.sm_bios_entry_64 = entry_64;
+        self.set_capability(CAP_SMBIOS, !entry_32.is_null() || !entry_64.is_null());
+        self
+    }
+
+    /// Sets [`BootloaderResponse::uefi_system_table_ptr`].
+    pub fn uefi_system_table(&mut self, table: *const c_void) -> &mut Self {
+        self.response.uefi_system_table_ptr = table;
+        self
+    }
+
+    /// Sets [`BootloaderResponse::uefi_runtime_available`].
+    pub fn uefi_runtime_available(&mut self, available: bool) -> &mut Self {
+        self.response.uefi_runtime_available = available.into();
+        self.set_capability(CAP_UEFI_RUNTIME, available);
+        self
+    }
+
+    /// Points the UEFI memory map at the `size` bytes starting at `map`, made up of
+    /// descriptors of `descriptor_size` bytes in the layout of `descriptor_version`.
+    pub fn uefi_memory_map(
+        &mut self,
+        map: *const c_void,
+        size: usize,
+        descriptor_size: usize,
+        descriptor_version: UefiDescriptorVersion,
+    ) -> &mut Self {
+        self.response.uefi_memory_map = map;
+        self.response.uefi_memory_map_size = size;
+        self.response.uefi_memory_map_descriptor_size = descriptor_size;
+        self.response.uefi_memory_map_descriptor_version = descriptor_version.0;
+        self
+    }
+
+    /// Appends `extension` to the end of the list of extensions.
+    ///
+    /// This sets [`ExtensionHeader::next`] of `extension` to NULL and links the previously
+    /// added block, if any, to it, so the blocks are listed in the order they were added.
+    pub fn add_extension(&mut self, extension: &'a mut ExtensionHeader) -> &mut Self {
+        extension.next = core::ptr::null();
+        let extension: *mut ExtensionHeader = extension;
+
+        if self.last_extension.is_null() {
+            self.response.extensions = extension;
+        } else {
+            // SAFETY: `last_extension` was borrowed mutably for `'a` by the previous call, so it
+            // is valid and nothing else can access it.
+            unsafe { (*self.last_extension).next = extension };
+        }
+        self.last_extension = extension;
+        self
+    }
+
+    /// Validates that the response built so far is self-consistent.
+    ///
+    /// # Errors
+    /// - [`BuildError::InconsistentLength`]: an array or string has a NULL pointer with a
+    ///   non-zero length, or a non-NULL pointer with a length of 0.
+    /// - [`BuildError::MemoryMap`]: the memory map fails [`memory_map::validate_memory_map`].
+    /// - [`BuildError::Module`]: the modules fail [`module::validate_module_pointers`].
+    pub fn validate(&self) -> Result<(), BuildError> {
+        let response = &self.response;
+        let lengths = [
+            (
+                "bootloader_name",
+                response.bootloader_name.is_null(),
+                response.bootloader_name_length,
+            ),
+            (
+                "bootloader_version",
+                response.bootloader_version.is_null(),
+                response.bootloader_version_length,
+            ),
+            (
+                "memory_map_entries",
+                response.memory_map_entries.is_null(),
+                response.memory_map_entry_count,
+            ),
+            (
+                "uefi_memory_map",
+                response.uefi_memory_map.is_null(),
+                response.uefi_memory_map_size,
+            ),
+            (
+                "module_entries",
+                response.module_entries.is_null(),
+                response.module_entry_count,
+            ),
+            (
+                "protocol_revision",
+                response.protocol_revision.is_null(),
+                response.protocol_revision_length,
+            ),
+            (
+                "framebuffers",
+                response.framebuffers.is_null(),
+                response.framebuffer_count,
+            ),
+            (
+                "firmware_vendor",
+                response.firmware_vendor.is_null(),
+                response.firmware_vendor_length,
+            ),
+        ];
+        if let Some(&(field, ..)) = lengths
+            .iter()
+            .find(|&&(_, null, length)| null != (length == 0))
+        {
+            return Err(BuildError::InconsistentLength { field });
+        }
+
+        // SAFETY: the memory map and modules are either empty or were set from slices that
+        // are borrowed for `'a`.
+        let (memory_map, modules) = unsafe { (response.memory_map(), response.modules()) };
+        memory_map::validate_memory_map(memory_map).map_err(BuildError::MemoryMap)?;
+        module::validate_module_pointers(modules).map_err(BuildError::Module)
+    }
+
+    /// Returns the built [`BootloaderResponse`].
+    ///
+    /// In debug builds, this panics if [`BootloaderResponseBuilder::validate`] fails, so that
+    /// mistakes are caught during development. Release builds skip the check entirely, and so
+    /// may return an inconsistent response; call [`BootloaderResponseBuilder::validate`]
+    /// directly to check a response in release builds.
+    pub fn build(&self) -> BootloaderResponse {
+        debug_assert_eq!(self.validate(), Ok(()), "inconsistent bootloader response");
+
+        self.response
+    }
+}
+
+impl Default for BootloaderResponseBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{entry, framebuffer, module},
+        MemoryMapEntryKind,
+    };
+
+    #[test]
+    fn build_fills_fields() {
+        let mut entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x10_0000),
+            entry(MemoryMapEntryKind::KERNEL, 0x10_0000, 0x4000),
+        ];
+        let mut modules = [module("initrd", 0x20_0000 as *const u8, 0x1000)];
+        let mut framebuffers = [framebuffer(0x8000_0000 as *mut u8, 640, 480)];
+
+        let response = BootloaderResponseBuilder::new()
+            .bootloader_name("capora")
+            .bootloader_version("")
+            .kernel(
+                VirtAddr::new(0xffff_ffff_8000_0000),
+                VirtAddr::new(0xffff_ffff_8000_1000),
+            )
+            .kernel_physical(0x10_0000, 0x4000)
+            .memory_map(&mut entries)
+            .modules(&mut modules)
+            .framebuffers(&mut framebuffers)
+            .build();
+
+        assert_eq!(response.signature, RESPONSE_SIGNATURE);
+        assert_eq!(response.api_version, API_VERSION);
+        // SAFETY: every pointer in `response` points into a local or a `'static` string, all
+        // of which outlive `response`.
+        unsafe {
+            assert_eq!(response.bootloader_name(), Ok("capora"));
+            assert!(response.bootloader_version.is_null());
+            assert_eq!(response.memory_map(), &entries);
+            assert_eq!(response.modules(), &modules);
+            assert_eq!(response.framebuffers(), &framebuffers);
+        }
+        assert_eq!(response.kernel_physical_range(), Some(0x10_0000..0x10_4000));
+    }
+
+    #[test]
+    fn validate_reports_inconsistencies() {
+        let mut builder = BootloaderResponseBuilder::new();
+        assert_eq!(builder.validate(), Ok(()));
+
+        let map = [0u8; 48];
+        builder.uefi_memory_map(map.as_ptr().cast(), 0, 48, UefiDescriptorVersion::V1);
+        assert_eq!(
+            builder.validate(),
+            Err(BuildError::InconsistentLength {
+                field: "uefi_memory_map"
+            })
+        );
+
+        builder.uefi_memory_map(map.as_ptr().cast(), 48, 48, UefiDescriptorVersion::V1);
+        let mut entries = [entry(MemoryMapEntryKind::USABLE, 0x1000, 0x800)];
+        builder.memory_map(&mut entries);
+        assert_eq!(
+            builder.validate(),
+            Err(BuildError::MemoryMap(MemoryMapError::Unaligned {
+                index: 0
+            }))
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inconsistent bootloader response")]
+    fn build_panics_on_inconsistent_response_in_debug() {
+        let map = [0u8; 48];
+        BootloaderResponseBuilder::new()
+            .uefi_memory_map(map.as_ptr().cast(), 0, 48, UefiDescriptorVersion::V1)
+            .build();
+    }
+
+    #[test]
+    fn build_stamps_protocol_revision() {
+        let response = BootloaderResponseBuilder::new().build();
+
+        // SAFETY: the protocol revision points to `PROTOCOL_REVISION`, which is `'static`.
+        assert_eq!(
+            unsafe { response.protocol_revision() },
+            Ok(PROTOCOL_REVISION)
+        );
+    }
+
+    #[test]
+    fn capabilities_follow_populated_fields() {
+        let (rsdp, sm_bios) = ([0u8; 36], [0u8; 24]);
+        let mut framebuffers = [framebuffer(0x8000_0000 as *mut u8, 640, 480)];
+        let mut builder = BootloaderResponseBuilder::new();
+        assert_eq!(builder.build().capabilities, 0);
+
+        builder
+            .rsdp(rsdp.as_ptr().cast(), 0xe_0000)
+            .sm_bios(core::ptr::null(), sm_bios.as_ptr().cast())
+            .framebuffers(&mut framebuffers)
+            .uefi_runtime_available(true);
+        let response = builder.build();
+        assert!(response.has_capability(CAP_RSDP | CAP_SMBIOS | CAP_FRAMEBUFFER | CAP_UEFI_RUNTIME));
+        assert!(!response.has_capability(crate::CAP_COMMAND_LINE));
+
+        builder
+            .rsdp(core::ptr::null(), 0)
+            .sm_bios(core::ptr::null(), core::ptr::null())
+            .framebuffers(&mut [])
+            .uefi_runtime_available(false);
+        assert_eq!(builder.build().capabilities, 0);
+    }
+
+    #[test]
+    fn memory_map_from_iter_sorts_and_bounds() {
+        let entries = [
+            entry(MemoryMapEntryKind::RESERVED, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x3000, 0x1000),
+        ];
+
+        let mut small = [entry(MemoryMapEntryKind::USABLE, 0, 0); 2];
+        let mut builder = BootloaderResponseBuilder::new();
+        assert_eq!(
+            builder.memory_map_from_iter(entries, &mut small),
+            Err(BuildError::MemoryMap(MemoryMapError::BufferTooSmall {
+                index: 2
+            }))
+        );
+        assert_eq!(builder.build().memory_map_entry_count, 0);
+
+        let mut buf = [entry(MemoryMapEntryKind::USABLE, 0, 0); 4];
+        assert_eq!(builder.memory_map_from_iter(entries, &mut buf), Ok(()));
+        let response = builder.build();
+        // SAFETY: the memory map points to `buf`, which outlives `response`.
+        assert_eq!(
+            unsafe { response.memory_map() },
+            [entries[1], entries[0], entries[2]]
+        );
+        assert_eq!(response.memory_map_entries.cast_const(), buf.as_ptr());
+    }
+
+    #[test]
+    fn extensions_are_listed_in_order() {
+        let mut first = ExtensionHeader {
+            next: core::ptr::null(),
+            kind: 1,
+            size: core::mem::size_of::<ExtensionHeader>() as u64,
+        };
+        let mut second = ExtensionHeader { kind: 2, ..first };
+
+        let response = BootloaderResponseBuilder::new()
+            .add_extension(&mut first)
+            .add_extension(&mut second)
+            .build();
+
+        // SAFETY: the extensions point to `first` and `second`, which outlive `response`.
+        let kinds = unsafe { response.extensions() }
+            .map(|extension| extension.kind)
+            .collect::<std::vec::Vec<_>>();
+        assert_eq!(kinds, [1, 2]);
+    }
+
+    #[test]
+    fn memory_map_sets_checksum() {
+        let mut entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x10_0000),
+            entry(MemoryMapEntryKind::KERNEL, 0x10_0000, 0x4000),
+        ];
+        let expected = memory_map::checksum(&entries);
+
+        let empty = BootloaderResponseBuilder::new().build();
+        assert_eq!(empty.memory_map_checksum(), Some(memory_map::checksum(&[])));
+
+        let response = BootloaderResponseBuilder::new()
+            .memory_map(&mut entries)
+            .build();
+        assert_eq!(response.memory_map_checksum(), Some(expected));
+        assert_ne!(expected, memory_map::checksum(&[]));
+
+        // SAFETY: the memory map points to `entries`, which outlives `response`.
+        assert!(unsafe { response.audit() }.passed());
+    }
+}
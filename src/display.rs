@@ -0,0 +1,275 @@
+//! Formatting helpers for describing the contents of a [`BootloaderResponse`].
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::{fmt, str::Utf8Error};
+
+#[cfg(feature = "alloc")]
+use crate::MemoryMapEntry;
+use crate::{BootloaderResponse, MemoryMapEntryKind};
+
+/// A size, in bytes, that displays in a human-readable form such as `12.5 MiB`.
+///
+/// Sizes of at least 1 KiB are displayed with a single, truncated decimal place using integer
+/// arithmetic, so no floating point support is required.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HumanSize(pub u64);
+
+impl fmt::Display for HumanSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+        let bytes = self.0;
+        let Some((exponent, name)) = UNITS
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|&(exponent, _)| bytes >> (10 * (exponent + 1)) != 0)
+        else {
+            return write!(f, "{bytes} B");
+        };
+
+        let shift = 10 * (exponent + 1);
+        let whole = bytes >> shift;
+        let fraction = ((bytes & ((1 << shift) - 1)) * 10) >> shift;
+        write!(f, "{whole}.{fraction} {name}")
+    }
+}
+
+impl MemoryMapEntryKind {
+    /// Returns the name of this [`MemoryMapEntryKind`], as spelled by its constant, or
+    /// `"UNKNOWN"` if it is not defined by this crate.
+    pub const fn name(&self) -> &'static str {
+        match *self {
+            Self::USABLE => "USABLE",
+            Self::RESERVED => "RESERVED",
+            Self::ACPI_RECLAIMABLE => "ACPI_RECLAIMABLE",
+            Self::ACPI_NONVOLATILE_STORAGE => "ACPI_NONVOLATILE_STORAGE",
+            Self::UNUSABLE => "UNUSABLE",
+            Self::UNACCEPTED => "UNACCEPTED",
+            Self::BOOTLOADER => "BOOTLOADER",
+            Self::KERNEL => "KERNEL",
+            Self::MODULE => "MODULE",
+            _ => "UNKNOWN",
+        }
+    }
+
+    /// Returns the stable, lowercase name of this [`MemoryMapEntryKind`], such as
+    /// `"acpi_reclaimable"`, or `"unknown"` if it is not defined by this crate.
+    ///
+    /// Unlike [`MemoryMapEntryKind::name()`], these names are intended for machine-parseable
+    /// logs and will not change between releases.
+    pub const fn abi_str(&self) -> &'static str {
+        match *self {
+            Self::USABLE => "usable",
+            Self::RESERVED => "reserved",
+            Self::ACPI_RECLAIMABLE => "acpi_reclaimable",
+            Self::ACPI_NONVOLATILE_STORAGE => "acpi_nonvolatile_storage",
+            Self::UNUSABLE => "unusable",
+            Self::UNACCEPTED => "unaccepted",
+            Self::BOOTLOADER => "bootloader",
+            Self::KERNEL => "kernel",
+            Self::MODULE => "module",
+            _ => "unknown",
+        }
+    }
+}
+
+/// An output device to which a [`BootloaderResponse`] can be dumped line by line.
+pub trait ResponseSink {
+    /// Writes a single line, without a trailing newline, to the sink.
+    fn write_line(&mut self, args: fmt::Arguments);
+}
+
+/// Returns `string`, or a placeholder if it is not valid utf-8.
+fn or_invalid(string: Result<&str, Utf8Error>) -> &str {
+    string.unwrap_or("<invalid utf-8>")
+}
+
+impl BootloaderResponse {
+    /// Writes the strings, memory map, and modules of this [`BootloaderResponse`] to `sink`,
+    /// one line at a time.
+    ///
+    /// # Safety
+    /// Every pointer in `self` must uphold the requirements of the corresponding
+    /// [`BootloaderResponse`] accessor.
+    pub unsafe fn dump_to(&self, sink: &mut impl ResponseSink) {
+        // SAFETY: the caller guarantees that every pointer in `self` is valid.
+        let (name, version, revision, vendor, memory_map, modules) = unsafe {
+            (
+                or_invalid(self.bootloader_name()),
+                or_invalid(self.bootloader_version()),
+                or_invalid(self.protocol_revision()),
+                or_invalid(self.firmware_vendor()),
+                self.memory_map(),
+                self.modules(),
+            )
+        };
+
+        sink.write_line(format_args!("bootloader: {name} {version}"));
+        sink.write_line(format_args!("protocol revision: {revision}"));
+        sink.write_line(format_args!("firmware vendor: {vendor}"));
+        sink.write_line(format_args!("api version: {}", self.api_version));
+
+        sink.write_line(format_args!("memory map: {} entries", memory_map.len()));
+        for (index, entry) in memory_map.iter().enumerate() {
+            sink.write_line(format_args!(
+                "  {index}: {:#018x}-{:#018x} {} ({})",
+                entry.base,
+                entry.end_addr().as_u64(),
+                entry.kind.name(),
+                HumanSize(entry.size),
+            ));
+        }
+
+        sink.write_line(format_args!("modules: {} entries", modules.len()));
+        for (index, module) in modules.iter().enumerate() {
+            // SAFETY: the caller guarantees that the module names are valid.
+            let name = or_invalid(unsafe { module.name() });
+            sink.write_line(format_args!(
+                "  {index}: {name} at {:p} ({})",
+                module.address,
+                HumanSize(module.size as u64),
+            ));
+        }
+    }
+}
+
+/// Formats `entries` as CSV with a `kind,base,size` header, one row per entry.
+///
+/// Each kind is written as its [`MemoryMapEntryKind::name()`], and each base and size in
+/// hexadecimal.
+#[cfg(feature = "alloc")]
+pub fn memory_map_to_csv(entries: &[MemoryMapEntry]) -> String {
+    use fmt::Write;
+
+    let mut csv = String::from("kind,base,size\n");
+    for entry in entries {
+        // Writing to a `String` cannot fail.
+        let _ = writeln!(
+            csv,
+            "{},{:#x},{:#x}",
+            entry.kind.name(),
+            entry.base,
+            entry.size
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use std::string::ToString;
+    #[cfg(feature = "alloc")]
+    use std::{string::String, vec, vec::Vec};
+
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use crate::test_util::{entry, module, response, set_memory_map, set_modules};
+
+    #[test]
+    fn human_size_exact() {
+        assert_eq!(HumanSize(0).to_string(), "0 B");
+        assert_eq!(HumanSize(1023).to_string(), "1023 B");
+        assert_eq!(HumanSize(1024).to_string(), "1.0 KiB");
+        assert_eq!(HumanSize(4 << 20).to_string(), "4.0 MiB");
+        assert_eq!(HumanSize(1 << 40).to_string(), "1.0 TiB");
+    }
+
+    #[test]
+    fn human_size_fractional() {
+        assert_eq!(HumanSize(1536).to_string(), "1.5 KiB");
+        assert_eq!(HumanSize((12 << 20) + (1 << 19)).to_string(), "12.5 MiB");
+        assert_eq!(HumanSize((1 << 30) - 1).to_string(), "1023.9 MiB");
+        assert_eq!(HumanSize(u64::MAX).to_string(), "16777215.9 TiB");
+    }
+
+    /// A [`ResponseSink`] that collects every line it is given.
+    #[cfg(feature = "alloc")]
+    struct Lines(Vec<String>);
+
+    #[cfg(feature = "alloc")]
+    impl ResponseSink for Lines {
+        fn write_line(&mut self, args: fmt::Arguments) {
+            self.0.push(std::fmt::format(args));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn dump_to_writes_every_line() {
+        let name = "capora";
+        let version = "1.0";
+        let mut entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x10_0000),
+            entry(MemoryMapEntryKind::KERNEL, 0x10_0000, 0x4000),
+        ];
+        let mut modules = [module("initrd", 0x20_0000 as *const u8, 0x800)];
+        let mut response = response();
+        response.bootloader_name = name.as_ptr();
+        response.bootloader_name_length = name.len();
+        response.bootloader_version = version.as_ptr();
+        response.bootloader_version_length = version.len();
+        set_memory_map(&mut response, &mut entries);
+        set_modules(&mut response, &mut modules);
+
+        let mut lines = Lines(Vec::new());
+        // SAFETY: every pointer in `response` points into a local or a `'static` string, all of
+        // which outlive `response`. The module itself is never read.
+        unsafe { response.dump_to(&mut lines) };
+
+        assert_eq!(
+            lines.0,
+            vec![
+                "bootloader: capora 1.0".to_string(),
+                "protocol revision: ".to_string(),
+                "firmware vendor: ".to_string(),
+                std::format!("api version: {}", crate::API_VERSION),
+                "memory map: 2 entries".to_string(),
+                "  0: 0x0000000000000000-0x0000000000100000 USABLE (1.0 MiB)".to_string(),
+                "  1: 0x0000000000100000-0x0000000000104000 KERNEL (16.0 KiB)".to_string(),
+                "modules: 1 entries".to_string(),
+                "  0: initrd at 0x200000 (2.0 KiB)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn memory_map_csv_rows() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x9f000),
+            entry(MemoryMapEntryKind::ACPI_RECLAIMABLE, 0x10_0000, 0x1000),
+        ];
+
+        assert_eq!(
+            memory_map_to_csv(&entries),
+            "kind,base,size\nUSABLE,0x0,0x9f000\nACPI_RECLAIMABLE,0x100000,0x1000\n"
+        );
+    }
+
+    #[test]
+    fn abi_strs_are_distinct() {
+        let kinds = [
+            MemoryMapEntryKind::USABLE,
+            MemoryMapEntryKind::RESERVED,
+            MemoryMapEntryKind::ACPI_RECLAIMABLE,
+            MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE,
+            MemoryMapEntryKind::UNUSABLE,
+            MemoryMapEntryKind::UNACCEPTED,
+            MemoryMapEntryKind::BOOTLOADER,
+            MemoryMapEntryKind::KERNEL,
+            MemoryMapEntryKind::MODULE,
+        ];
+
+        let names = kinds
+            .iter()
+            .map(MemoryMapEntryKind::abi_str)
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(names.len(), kinds.len());
+        assert!(!names.contains("unknown"));
+        for kind in kinds {
+            assert_eq!(kind.abi_str(), kind.name().to_lowercase());
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Detection of which [`BootloaderResponse`] fields are valid for a given API version.
+
+use crate::BootloaderResponse;
+
+/// A set of [`BootloaderResponse`] fields that were introduced after the first supported API
+/// version.
+///
+/// Fields not covered by a [`FieldMask`] constant are present in every supported API version.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FieldMask(pub u64);
+
+impl FieldMask {
+    /// The [`FieldMask`] that contains no fields.
+    pub const EMPTY: Self = Self(0);
+    /// [`BootloaderResponse::kaslr_slide`], introduced in API version 10.
+    pub const KASLR_SLIDE: Self = Self(1 << 0);
+    /// [`BootloaderResponse::rsdp_physical_address`], introduced in API version 12.
+    pub const RSDP_PHYSICAL_ADDRESS: Self = Self(1 << 1);
+    /// [`BootloaderResponse::firmware_vendor`] and its length, introduced in API version 13.
+    pub const FIRMWARE_VENDOR: Self = Self(1 << 2);
+    /// The [`ModuleKind`](crate::ModuleKind) stored in every
+    /// [`ModuleEntry::flags`](crate::ModuleEntry::flags), introduced in API version 14.
+    pub const MODULE_KINDS: Self = Self(1 << 3);
+    /// [`BootloaderResponse::kernel_physical_address`] and
+    /// [`BootloaderResponse::kernel_size`], introduced in API version 15.
+    pub const KERNEL_PHYSICAL: Self = Self(1 << 4);
+    /// [`BootloaderResponse::extensions`], introduced in API version 16.
+    pub const EXTENSIONS: Self = Self(1 << 5);
+    /// [`BootloaderResponse::memory_map_checksum`], introduced in API version 17.
+    pub const MEMORY_MAP_CHECKSUM: Self = Self(1 << 6);
+
+    /// Every version-gated field paired with the API version in which it was introduced.
+    const INTRODUCED: [(Self, u64); 7] = [
+        (Self::KASLR_SLIDE, 10),
+        (Self::RSDP_PHYSICAL_ADDRESS, 12),
+        (Self::FIRMWARE_VENDOR, 13),
+        (Self::MODULE_KINDS, 14),
+        (Self::KERNEL_PHYSICAL, 15),
+        (Self::EXTENSIONS, 16),
+        (Self::MEMORY_MAP_CHECKSUM, 17),
+    ];
+
+    /// Returns the [`FieldMask`] of the fields that are valid in a response constructed using
+    /// `api_version`.
+    pub const fn for_version(api_version: u64) -> Self {
+        let mut mask = Self::EMPTY;
+        let mut index = 0;
+        while index < Self::INTRODUCED.len() {
+            let (field, introduced) = Self::INTRODUCED[index];
+            if introduced <= api_version {
+                mask = mask.union(field);
+            }
+            index += 1;
+        }
+        mask
+    }
+
+    /// Returns the union of `self` and `other`.
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns `true` if `self` contains every field in `other`.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BootloaderResponse {
+    /// Returns the [`FieldMask`] of the fields that are valid given
+    /// [`BootloaderResponse::api_version`].
+    ///
+    /// The accessors of version-gated fields return a default value when the field is not
+    /// valid.
+    pub const fn available_fields(&self) -> FieldMask {
+        FieldMask::for_version(self.api_version)
+    }
+
+    /// Returns `true` if every field in `fields` is valid for this response.
+    pub(crate) const fn has_fields(&self, fields: FieldMask) -> bool {
+        self.available_fields().contains(fields)
+    }
+
+    /// Returns `value` if every field in `fields` is valid for this response, or the default
+    /// value of `T` otherwise.
+    pub(crate) fn gated<T: Default>(&self, fields: FieldMask, value: T) -> T {
+        if self.has_fields(fields) {
+            value
+        } else {
+            T::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::response, MIN_SUPPORTED_API_VERSION};
+
+    #[test]
+    fn for_version() {
+        assert_eq!(
+            FieldMask::for_version(MIN_SUPPORTED_API_VERSION),
+            FieldMask::EMPTY
+        );
+        assert!(!FieldMask::for_version(MIN_SUPPORTED_API_VERSION).contains(FieldMask::KASLR_SLIDE));
+        assert!(FieldMask::for_version(crate::API_VERSION).contains(FieldMask::FIRMWARE_VENDOR));
+    }
+
+    #[test]
+    fn older_version_hides_newer_fields() {
+        let vendor = "EDK II";
+        let rsdp = [0u8; 36];
+        let mut response = response();
+        response.api_version = MIN_SUPPORTED_API_VERSION;
+        response.kaslr_slide = 0x20_0000;
+        response.rsdp_table_ptr = rsdp.as_ptr().cast();
+        response.rsdp_physical_address = 0xe_0000;
+        response.firmware_vendor = vendor.as_ptr();
+        response.firmware_vendor_length = vendor.len();
+        response.paging_levels = 4;
+
+        assert!(!response.has_fields(FieldMask::KASLR_SLIDE));
+        assert_eq!(response.kaslr_slide(), 0);
+        assert_eq!(response.rsdp_physical(), None);
+        assert_eq!(response.paging_levels(), 4);
+        // SAFETY: the vendor points to `vendor`, which outlives `response`.
+        assert_eq!(unsafe { response.firmware_vendor() }, Ok(""));
+        // SAFETY: `response` has no arrays.
+        assert_eq!(
+            unsafe { response.copy_size() },
+            core::mem::size_of::<crate::BootloaderResponse>()
+                + core::mem::align_of::<crate::BootloaderResponse>()
+                - 1
+        );
+
+        let scalars = response.scalars();
+        assert_eq!(scalars.kaslr_slide, 0);
+        assert_eq!(scalars.rsdp_physical_address, 0);
+        assert_eq!(scalars.firmware_vendor_length, 0);
+        assert_eq!(scalars.paging_levels, 4);
+    }
+}
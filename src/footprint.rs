@@ -0,0 +1,462 @@
+//! Helpers for locating and copying the memory occupied by a [`BootloaderResponse`].
+
+use core::{marker::PhantomData, ops::Range};
+
+use crate::{
+    address::DirectMapOffset,
+    fields::FieldMask,
+    memory_map::{self, MemoryKindSet},
+    BootloaderResponse, MemoryMapEntry, MemoryMapEntryKind, ModuleEntry,
+};
+
+/// An error returned when a buffer is too small to hold a copy of a [`BootloaderResponse`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BufferTooSmall;
+
+impl core::fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("buffer is too small to hold the bootloader response")
+    }
+}
+
+/// A bump allocator over a byte buffer used to deep copy a [`BootloaderResponse`].
+///
+/// The buffer is held as a raw pointer so that every pointer handed out is derived from the
+/// same borrow, and so stays valid while later copies are made.
+struct Bump<'a> {
+    base: *mut u8,
+    len: usize,
+    offset: usize,
+    buf: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> Bump<'a> {
+    /// Creates a new [`Bump`] allocating from the start of `buf`.
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self {
+            base: buf.as_mut_ptr(),
+            len: buf.len(),
+            offset: 0,
+            buf: PhantomData,
+        }
+    }
+
+    /// Copies `src` into the buffer, returning a pointer to the copy, or NULL if `src` is
+    /// empty.
+    fn copy<T: Copy>(&mut self, src: &[T]) -> Result<*mut T, BufferTooSmall> {
+        if src.is_empty() {
+            return Ok(core::ptr::null_mut());
+        }
+
+        let start = self
+            .base
+            .wrapping_add(self.offset)
+            .align_offset(core::mem::align_of::<T>())
+            .checked_add(self.offset)
+            .ok_or(BufferTooSmall)?;
+        let end = core::mem::size_of_val(src)
+            .checked_add(start)
+            .ok_or(BufferTooSmall)?;
+        if end > self.len {
+            return Err(BufferTooSmall);
+        }
+
+        // SAFETY: `start..end` lies within the buffer, so the offset stays in bounds.
+        let dst = unsafe { self.base.add(start) }.cast::<T>();
+        // SAFETY: `dst` is aligned for `T` and valid for `src.len()` writes, and cannot overlap
+        // `src` since it is derived from a mutable borrow.
+        unsafe { core::ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len()) };
+        self.offset = end;
+        Ok(dst)
+    }
+}
+
+/// Returns the number of bytes required to copy `len` values of `T` into an arbitrarily
+/// aligned buffer.
+fn padded_size_of<T>(len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+
+    (core::mem::align_of::<T>() - 1).saturating_add(len.saturating_mul(core::mem::size_of::<T>()))
+}
+
+/// Returns the physical address range of `length` values of `T` starting at `ptr`, translated
+/// through `direct_map`.
+fn range_of<T>(direct_map: DirectMapOffset, ptr: *const T, length: usize) -> Range<u64> {
+    let start = direct_map.virt_to_phys(ptr as usize);
+    start..start.saturating_add(length.saturating_mul(core::mem::size_of::<T>()) as u64)
+}
+
+impl BootloaderResponse {
+    /// Returns an iterator over the physical address ranges occupied by this
+    /// [`BootloaderResponse`], its memory map array, its module array, its framebuffer array,
+    /// its UEFI memory map, and every string it references.
+    ///
+    /// Addresses are translated to physical addresses through
+    /// [`BootloaderResponse::direct_map`], as in [`BootloaderResponse::response_region`], so
+    /// that the ranges can be compared against [`MemoryMapEntryKind::BOOTLOADER`] entries.
+    ///
+    /// The kernel must copy everything it needs out of these ranges before reclaiming the
+    /// [`MemoryMapEntryKind::BOOTLOADER`] memory that contains them. Empty ranges are not
+    /// yielded.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules()`] apply.
+    pub unsafe fn own_footprint(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let modules = unsafe { self.modules() };
+        let direct_map = self.direct_map();
+
+        let [name, version, revision, vendor] = self.strings();
+        [
+            range_of(direct_map, self as *const Self, 1),
+            range_of(
+                direct_map,
+                self.memory_map_entries,
+                self.memory_map_entry_count,
+            ),
+            range_of(direct_map, self.module_entries, self.module_entry_count),
+            range_of(direct_map, self.framebuffers, self.framebuffer_count),
+            range_of(
+                direct_map,
+                self.uefi_memory_map.cast::<u8>(),
+                self.uefi_memory_map_size,
+            ),
+            range_of(direct_map, name.0, name.1),
+            range_of(direct_map, version.0, version.1),
+            range_of(direct_map, revision.0, revision.1),
+            range_of(direct_map, vendor.0, vendor.1),
+        ]
+        .into_iter()
+        .chain(
+            modules
+                .iter()
+                .map(move |module| range_of(direct_map, module.name, module.name_length)),
+        )
+        .filter(|range| !range.is_empty())
+    }
+}
+
+impl BootloaderResponse {
+    /// Returns a number of bytes that is always sufficient for [`BootloaderResponse::copy_into`]
+    /// to deep copy this [`BootloaderResponse`].
+    ///
+    /// This is the size of the [`BootloaderResponse`] itself, the memory map, module, and
+    /// framebuffer arrays, the UEFI memory map, and every string the response references,
+    /// plus the most padding that could be needed to align them. The response returned by
+    /// [`BootloaderResponse::copy_into`] can therefore be stored in the same allocation,
+    /// after the bytes `copy_into` used.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules()`] apply.
+    pub unsafe fn copy_size(&self) -> usize {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let modules = unsafe { self.modules() };
+
+        [
+            padded_size_of::<BootloaderResponse>(1),
+            padded_size_of::<crate::MemoryMapEntry>(self.memory_map_entry_count),
+            padded_size_of::<ModuleEntry>(self.module_entry_count),
+            padded_size_of::<crate::Framebuffer>(self.framebuffer_count),
+            self.uefi_memory_map_size,
+        ]
+        .into_iter()
+        .chain(self.strings().map(|(_, length)| length))
+        .chain(modules.iter().map(|module| module.name_length))
+        .fold(0, usize::saturating_add)
+    }
+
+    /// Deep copies the arrays and strings referenced by this [`BootloaderResponse`] into
+    /// `buf`, returning a [`BootloaderResponse`] that points into `buf` instead.
+    ///
+    /// Module data is not copied, since it does not live in
+    /// [`MemoryMapEntryKind::BOOTLOADER`] memory. Extension blocks are not copied either, so
+    /// the copy shares the extensions of `self`. Empty arrays and strings are replaced with
+    /// NULL.
+    ///
+    /// # Errors
+    /// Returns [`BufferTooSmall`] if `buf` cannot hold the copy, which never happens if `buf`
+    /// is at least [`BootloaderResponse::copy_size`] bytes long.
+    ///
+    /// # Safety
+    /// Every pointer in `self` must uphold the requirements of the corresponding
+    /// [`BootloaderResponse`] accessor.
+    pub unsafe fn copy_into(&self, buf: &mut [u8]) -> Result<BootloaderResponse, BufferTooSmall> {
+        let mut bump = Bump::new(buf);
+        let mut copy = *self;
+
+        // SAFETY: the caller guarantees that every pointer in `self` is valid.
+        unsafe {
+            copy.memory_map_entries = bump.copy(self.memory_map())?;
+            copy.framebuffers = bump.copy(self.framebuffers())?;
+            copy.uefi_memory_map = bump.copy(self.uefi_memory_map_bytes())?.cast();
+            let [name, version, revision, vendor] = self.strings();
+            copy.bootloader_name = bump.copy(bytes_of(name.0, name.1))?;
+            copy.bootloader_version = bump.copy(bytes_of(version.0, version.1))?;
+            copy.protocol_revision = bump.copy(bytes_of(revision.0, revision.1))?;
+            copy.firmware_vendor = bump.copy(bytes_of(vendor.0, vendor.1))?;
+        }
+
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let modules = unsafe { self.modules() };
+        let module_entries = bump.copy(modules)?;
+        for (index, module) in modules.iter().enumerate() {
+            // SAFETY: the caller guarantees that the module names are valid.
+            let name = bump.copy(unsafe { bytes_of(module.name, module.name_length) })?;
+            // SAFETY: `module_entries` points to a copy of `modules` in `buf`, so `index` is in
+            // bounds.
+            unsafe { (*module_entries.add(index)).name = name };
+        }
+        copy.module_entries = module_entries;
+
+        Ok(copy)
+    }
+}
+
+impl BootloaderResponse {
+    /// Returns the pointer and length of the bootloader name, bootloader version, protocol
+    /// revision, and firmware vendor, in that order.
+    ///
+    /// The length of a string is 0 if its field is not available.
+    fn strings(&self) -> [(*const u8, usize); 4] {
+        [
+            (self.bootloader_name, self.bootloader_name_length),
+            (self.bootloader_version, self.bootloader_version_length),
+            (self.protocol_revision, self.protocol_revision_length),
+            (
+                self.firmware_vendor,
+                self.gated(FieldMask::FIRMWARE_VENDOR, self.firmware_vendor_length),
+            ),
+        ]
+    }
+
+    /// Returns the bytes of the UEFI memory map, or an empty slice if
+    /// [`BootloaderResponse::uefi_memory_map`] is NULL.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::uefi_memory_map_descriptors()`] apply.
+    unsafe fn uefi_memory_map_bytes(&self) -> &[u8] {
+        if self.uefi_memory_map.is_null() {
+            return &[];
+        }
+
+        // SAFETY: the caller guarantees that the UEFI memory map is valid for the lifetime of
+        // `self`.
+        unsafe { bytes_of(self.uefi_memory_map.cast(), self.uefi_memory_map_size) }
+    }
+}
+
+/// Returns the `length` bytes starting at `ptr`, or an empty slice if `length` is 0.
+///
+/// # Safety
+/// If `length` is not 0, `ptr` must point to `length` readable bytes that remain valid for
+/// the lifetime `'a`.
+unsafe fn bytes_of<'a>(ptr: *const u8, length: usize) -> &'a [u8] {
+    if length == 0 {
+        return &[];
+    }
+
+    // SAFETY: the caller guarantees that `ptr` is valid for `length` bytes.
+    unsafe { core::slice::from_raw_parts(ptr, length) }
+}
+
+impl BootloaderResponse {
+    /// Returns the total size, in bytes, of the [`MemoryMapEntryKind::KERNEL`],
+    /// [`MemoryMapEntryKind::MODULE`], and [`MemoryMapEntryKind::BOOTLOADER`] regions.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::memory_map()`] apply.
+    pub unsafe fn reserved_footprint_bytes(&self) -> u64 {
+        let kinds = MemoryKindSet::EMPTY
+            .with(MemoryMapEntryKind::KERNEL)
+            .with(MemoryMapEntryKind::MODULE)
+            .with(MemoryMapEntryKind::BOOTLOADER);
+
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+        memory_map::total_bytes(unsafe { self.memory_map() }, kinds)
+    }
+
+    /// Returns the total size, in bytes, of the [`MemoryMapEntryKind::BOOTLOADER`] regions,
+    /// which the kernel can reclaim once it has copied out everything it needs.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::memory_map()`] apply.
+    pub unsafe fn reclaimable_footprint_bytes(&self) -> u64 {
+        let kinds = MemoryKindSet::EMPTY.with(MemoryMapEntryKind::BOOTLOADER);
+
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+        memory_map::total_bytes(unsafe { self.memory_map() }, kinds)
+    }
+}
+
+impl BootloaderResponse {
+    /// Returns the [`MemoryMapEntryKind::BOOTLOADER`] entry that contains this
+    /// [`BootloaderResponse`] itself.
+    ///
+    /// The address of `self` is translated to a physical address through
+    /// [`BootloaderResponse::direct_map`]. Returns [`None`] if no such entry exists.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::memory_map()`] apply.
+    pub unsafe fn response_region(&self) -> Option<&MemoryMapEntry> {
+        let address = self.direct_map().virt_to_phys(self as *const Self as usize);
+
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+        unsafe { self.memory_map() }.iter().find(|entry| {
+            entry.kind == MemoryMapEntryKind::BOOTLOADER
+                && address >= entry.base
+                && address - entry.base < entry.size
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::test_util::{entry, framebuffer, module, response, set_memory_map, set_modules};
+
+    /// Returns the physical range of `length` values of `T` at `ptr` given a direct map at
+    /// `direct_map`.
+    fn expected<T>(direct_map: usize, ptr: *const T, length: usize) -> Range<u64> {
+        let start = (ptr as usize - direct_map) as u64;
+        start..start + (length * core::mem::size_of::<T>()) as u64
+    }
+
+    #[test]
+    fn own_footprint_yields_every_buffer() {
+        const DIRECT_MAP: usize = 0x1000;
+
+        let (name, version, module_name) = ("capora", "1.0", "initrd");
+        let uefi_map = [0u8; 96];
+        let mut entries = [entry(MemoryMapEntryKind::USABLE, 0, 0x1000)];
+        let mut modules = [module(module_name, core::ptr::null(), 0)];
+
+        let mut response = response();
+        response.direct_map = DIRECT_MAP;
+        response.bootloader_name = name.as_ptr();
+        response.bootloader_name_length = name.len();
+        response.bootloader_version = version.as_ptr();
+        response.bootloader_version_length = version.len();
+        response.uefi_memory_map = uefi_map.as_ptr().cast();
+        response.uefi_memory_map_size = uefi_map.len();
+        set_memory_map(&mut response, &mut entries);
+        set_modules(&mut response, &mut modules);
+
+        // SAFETY: every pointer in `response` points to a local that outlives it.
+        let footprint = unsafe { response.own_footprint() }.collect::<Vec<_>>();
+        assert_eq!(
+            footprint,
+            [
+                expected(DIRECT_MAP, &response as *const BootloaderResponse, 1),
+                expected(DIRECT_MAP, entries.as_ptr(), entries.len()),
+                expected(DIRECT_MAP, modules.as_ptr(), modules.len()),
+                expected(DIRECT_MAP, uefi_map.as_ptr(), uefi_map.len()),
+                expected(DIRECT_MAP, name.as_ptr(), name.len()),
+                expected(DIRECT_MAP, version.as_ptr(), version.len()),
+                expected(DIRECT_MAP, module_name.as_ptr(), module_name.len()),
+            ]
+        );
+    }
+
+    #[test]
+    fn copy_into_round_trip() {
+        let (name, vendor, module_name) = ("capora", "EDK II", "initrd");
+        let uefi_map = [0x5a; 48];
+        let mut entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::BOOTLOADER, 0x1000, 0x1000),
+        ];
+        let mut modules = [module(module_name, 0x2000 as *const u8, 0x1000)];
+        let mut framebuffers = [framebuffer(0x3000 as *mut u8, 640, 480)];
+
+        let mut response = response();
+        response.bootloader_name = name.as_ptr();
+        response.bootloader_name_length = name.len();
+        response.firmware_vendor = vendor.as_ptr();
+        response.firmware_vendor_length = vendor.len();
+        response.uefi_memory_map = uefi_map.as_ptr().cast();
+        response.uefi_memory_map_size = uefi_map.len();
+        response.framebuffers = framebuffers.as_mut_ptr();
+        response.framebuffer_count = framebuffers.len();
+        set_memory_map(&mut response, &mut entries);
+        set_modules(&mut response, &mut modules);
+
+        // SAFETY: every pointer in `response` points to a local that outlives it.
+        let mut buf = std::vec![0; unsafe { response.copy_size() }];
+        // SAFETY: every pointer in `response` points to a local that outlives it.
+        let copy = unsafe { response.copy_into(&mut buf) }.unwrap();
+
+        let within = buf.as_ptr_range();
+        let within = within.start as usize..within.end as usize;
+        for address in [
+            copy.memory_map_entries as usize,
+            copy.module_entries as usize,
+            copy.framebuffers as usize,
+            copy.uefi_memory_map as usize,
+            copy.bootloader_name as usize,
+            copy.firmware_vendor as usize,
+        ] {
+            assert!(within.contains(&address));
+        }
+
+        // SAFETY: `copy` points into `buf`, which outlives it.
+        unsafe {
+            assert_eq!(copy.memory_map(), entries);
+            assert_eq!(copy.framebuffers(), framebuffers);
+            assert_eq!(copy.uefi_memory_map_bytes(), uefi_map);
+            assert_eq!(copy.bootloader_name(), Ok(name));
+            assert_eq!(copy.firmware_vendor(), Ok(vendor));
+            assert_eq!(copy.modules().len(), 1);
+            assert_eq!(copy.modules()[0].name(), Ok(module_name));
+            assert!(within.contains(&(copy.modules()[0].name as usize)));
+        }
+
+        let used = buf.len() - padded_size_of::<BootloaderResponse>(1);
+        // SAFETY: every pointer in `response` points to a local that outlives it.
+        assert!(unsafe { response.copy_into(&mut buf[..used]) }.is_ok());
+
+        // SAFETY: every pointer in `response` points to a local that outlives it.
+        let result = unsafe { response.copy_into(&mut buf[..name.len()]) };
+        assert!(matches!(result, Err(BufferTooSmall)));
+    }
+
+    #[test]
+    fn footprint_totals_sum_reserved_kinds() {
+        let mut entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x10_0000),
+            entry(MemoryMapEntryKind::KERNEL, 0x10_0000, 0x4000),
+            entry(MemoryMapEntryKind::MODULE, 0x10_4000, 0x2000),
+            entry(MemoryMapEntryKind::BOOTLOADER, 0x10_6000, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x10_7000, 0x1000),
+            entry(MemoryMapEntryKind::BOOTLOADER, 0x10_8000, 0x3000),
+        ];
+        let mut response = response();
+        set_memory_map(&mut response, &mut entries);
+
+        // SAFETY: the memory map points into `entries`, which outlives `response`.
+        unsafe {
+            assert_eq!(response.reserved_footprint_bytes(), 0xa000);
+            assert_eq!(response.reclaimable_footprint_bytes(), 0x4000);
+        }
+    }
+
+    #[test]
+    fn response_region_contains_the_response() {
+        let mut response = response();
+        let address = &response as *const BootloaderResponse as u64;
+        let base = address & !0xfff;
+        let mut entries = [
+            entry(MemoryMapEntryKind::BOOTLOADER, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, base - 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::BOOTLOADER, base, 0x2000),
+        ];
+        set_memory_map(&mut response, &mut entries);
+
+        // SAFETY: the memory map points to `entries`, which outlives `response`.
+        let region = unsafe { response.response_region() };
+        assert_eq!(region, Some(&entries[2]));
+    }
+}
@@ -1,4 +1,36 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod acpi;
+pub mod address;
+pub mod audit;
+pub mod builder;
+pub mod display;
+pub mod fields;
+pub mod footprint;
+pub mod memory_map;
+pub mod module;
+#[cfg(feature = "alloc")]
+pub mod owned;
+pub mod paging;
+pub mod request;
+mod smbios;
+pub mod summary;
+#[cfg(test)]
+mod test_util;
+pub mod uefi;
+pub mod util;
+pub mod view;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::str::Utf8Error;
+
+use address::{DirectMapOffset, PhysAddr, VirtAddr};
+use fields::FieldMask;
+use uefi::{UefiDescriptorVersion, UefiMemoryDescriptors};
 
 /// The signature that identifies the start of the [`BootloaderRequest`].
 pub const SIGNATURE: [u64; 3] = [
@@ -7,12 +39,119 @@ pub const SIGNATURE: [u64; 3] = [
     0b01000111_00000110_00110010_11010110_00110010_10010101_10110110_11110010,
 ];
 
+/// The signature that identifies the start of the [`BootloaderResponse`].
+pub const RESPONSE_SIGNATURE: [u64; 3] = [
+    0b10011011_11000000_10100001_10000011_00111111_10011000_10111110_01001111,
+    0b00111000_10101100_00110011_10011110_01110111_11101110_10101110_01011111,
+    0b11001100_11001000_11010010_01010011_10011100_00011110_11001110_11010010,
+];
+
 /// The version of the API that this currently describes.
-pub const API_VERSION: u64 = 0;
+pub const API_VERSION: u64 = 17;
+
+/// The lowest API version that this crate supports.
+///
+/// This is the first version in which [`BootloaderResponse`] starts with its signature and
+/// API version. Fields added since are gated by [`BootloaderResponse::available_fields`].
+pub const MIN_SUPPORTED_API_VERSION: u64 = 9;
+
+/// The highest API version that this crate supports.
+pub const MAX_SUPPORTED_API_VERSION: u64 = API_VERSION;
+
+/// Returns `true` if `version` is within the band of API versions this crate supports.
+pub const fn is_supported_version(version: u64) -> bool {
+    MIN_SUPPORTED_API_VERSION <= version && version <= MAX_SUPPORTED_API_VERSION
+}
+
+/// Returns `true` if this crate describes at least API version `min`.
+///
+/// This is usable in `const` contexts, so `const _: () = assert!(require_version(2));` fails
+/// the build when compiled against an older version of this crate. See
+/// [`require_api_version!`](crate::require_api_version) for a more readable alternative.
+pub const fn require_version(min: u64) -> bool {
+    API_VERSION >= min
+}
+
+/// Fails the build if this crate describes an API version older than the given one.
+#[macro_export]
+macro_rules! require_api_version {
+    ($min:expr) => {
+        const _: () = ::core::assert!(
+            $crate::require_version($min),
+            ::core::concat!(
+                "boot-api describes an API version older than ",
+                ::core::stringify!($min)
+            )
+        );
+    };
+}
+
+/// The revision of the boot protocol implemented by this build of the crate.
+///
+/// Unlike [`API_VERSION`], this identifies the exact crate release, which helps correlate a
+/// running kernel with the bootloader build that loaded it.
+pub const PROTOCOL_REVISION: &str = env!("CARGO_PKG_VERSION");
+
+/// A hash of the layout of every type shared between the bootloader and the kernel.
+///
+/// A kernel and bootloader can both embed this constant to detect whether they were compiled
+/// against diverging ABIs.
+pub const LAYOUT_HASH: u64 = layout_hash();
 
 /// The segment type that specifies the location of the the bootloader request.
 pub const BOOTLOADER_REQUEST_ELF_SEGMENT: u32 = 0x69B2BA6E;
 
+/// The size, in bytes, of the [`BootloaderRequest`] placed in the
+/// [`BOOTLOADER_REQUEST_ELF_SEGMENT`].
+pub const BOOTLOADER_REQUEST_SIZE: usize = core::mem::size_of::<BootloaderRequest>();
+
+const _: () = assert!(BOOTLOADER_REQUEST_SIZE == 5 * 8);
+
+/// Returns the `p_filesz` of the [`BOOTLOADER_REQUEST_ELF_SEGMENT`], which is exactly
+/// [`BOOTLOADER_REQUEST_SIZE`].
+pub const fn segment_filesz() -> u64 {
+    BOOTLOADER_REQUEST_SIZE as u64
+}
+
+/// The bootloader provided at least one [`Framebuffer`].
+pub const CAP_FRAMEBUFFER: u64 = 1 << 0;
+/// The bootloader provided [`BootloaderResponse::rsdp_table_ptr`].
+pub const CAP_RSDP: u64 = 1 << 1;
+/// The bootloader provided [`BootloaderResponse::sm_bios_entry_32`] or
+/// [`BootloaderResponse::sm_bios_entry_64`].
+pub const CAP_SMBIOS: u64 = 1 << 2;
+/// UEFI runtime services may be called, see [`BootloaderResponse::uefi_runtime_available`].
+pub const CAP_UEFI_RUNTIME: u64 = 1 << 3;
+/// The bootloader provided a kernel command line.
+///
+/// This bit is reserved until the response carries a command line.
+pub const CAP_COMMAND_LINE: u64 = 1 << 4;
+
+/// The largest number of memory map entries or modules that a non-corrupted
+/// [`BootloaderResponse`] is assumed to contain.
+///
+/// Real firmware memory maps hold at most a few hundred entries, so 65536 leaves ample room
+/// while bounding the slice constructed from a corrupted count to a few megabytes.
+pub const MAX_REASONABLE_ENTRIES: usize = 1 << 16;
+
+/// An error returned when a [`BootloaderResponse`] reports more than
+/// [`MAX_REASONABLE_ENTRIES`] entries in one of its arrays.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExcessiveCount {
+    /// The reported number of entries.
+    pub count: usize,
+}
+
+impl core::fmt::Display for ExcessiveCount {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} entries exceeds the limit of {MAX_REASONABLE_ENTRIES}",
+            self.count
+        )
+    }
+}
+
 /// Information that the kernel shares with the bootloader to allow the
 /// bootloader to properly load the kernel.
 #[repr(C)]
@@ -22,6 +161,14 @@ pub struct BootloaderRequest {
     pub signature: [u64; 3],
     /// The version of the API that this kernel expects to communicate using.
     pub api_version: u64,
+    /// A bitfield of the `CAP_*` flags describing the optional features the kernel asks the
+    /// bootloader to provide.
+    ///
+    /// The bootloader may still decline to provide a requested feature, so the kernel must
+    /// check [`BootloaderResponse::capabilities`] for what was actually provided.
+    ///
+    /// Introduced in API version 11.
+    pub requested_features: u64,
 }
 
 /// Information that the kernel requires to properly boot, to be passed
@@ -29,6 +176,13 @@ pub struct BootloaderRequest {
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BootloaderResponse {
+    /// The signature to indicate that this response is valid.
+    ///
+    /// This is always [`RESPONSE_SIGNATURE`].
+    pub signature: [u64; 3],
+    /// The version of the API that the bootloader used to construct this response.
+    pub api_version: u64,
+
     /// A utf-8 string containing the name of the loading bootloader.
     pub bootloader_name: *const u8,
     /// The length, in bytes, of [`BootloaderResponse::bootloader_name`].
@@ -42,7 +196,8 @@ pub struct BootloaderResponse {
     pub kernel_virtual_address: *const core::ffi::c_void,
     /// Offset of the higher half direct mapped memory.
     ///
-    /// This region of memory is mapped as readable, writable, and executable.
+    /// This region of memory is mapped as readable, writable, and executable. See
+    /// [`DirectMapOffset`] for translating addresses through it.
     pub direct_map: usize,
 
     /// An array of [`MemoryMapEntry`]s.
@@ -50,6 +205,7 @@ pub struct BootloaderResponse {
     /// The entries are guaranteed to be sorted by base address, lowest to highest.
     /// All regions are guaranteed to be 4096 byte aligned for both base and size.
     /// All regions are guaranteed to not overlap with any other entry.
+    /// All regions are guaranteed to have a non-zero size.
     pub memory_map_entries: *mut MemoryMapEntry,
     /// The number of [`MemoryMapEntry`]s to which [`BootloaderResponse::memory_map_entries`]
     /// points.
@@ -66,6 +222,9 @@ pub struct BootloaderResponse {
 
     /// A pointer to the ACPI RSDP table.
     ///
+    /// This is a virtual address within the direct map, see
+    /// [`BootloaderResponse::rsdp_physical_address`] for the corresponding physical address.
+    ///
     /// NULL if not found.
     pub rsdp_table_ptr: *const core::ffi::c_void,
     /// A pointer to the UEFI System Table.
@@ -80,12 +239,629 @@ pub struct BootloaderResponse {
     /// The size, in bytes, of the provided UEFI memory map descriptors.
     pub uefi_memory_map_descriptor_size: usize,
     /// The version of the provided UEFI memory map descriptors.
+    ///
+    /// See [`UefiDescriptorVersion`] for the versions this crate can interpret.
     pub uefi_memory_map_descriptor_version: u64,
 
     /// An array of [`ModuleEntry`]s.
     pub module_entries: *mut ModuleEntry,
     /// The number of [`ModuleEntry`]s to which [`BootloaderResponse::module_entries`] points.
     pub module_entry_count: usize,
+
+    /// A utf-8 string containing the [`PROTOCOL_REVISION`] the loading bootloader was built
+    /// against.
+    pub protocol_revision: *const u8,
+    /// The length, in bytes, of [`BootloaderResponse::protocol_revision`].
+    pub protocol_revision_length: usize,
+
+    /// The number of paging levels active upon kernel entry.
+    ///
+    /// On x86_64, this is 4 or 5, the latter indicating that 5-level paging (LA57) is enabled.
+    /// On other architectures, this is 0 to indicate that it is unknown or not applicable.
+    pub paging_levels: u8,
+
+    /// A bitfield of the `CAP_*` capabilities the bootloader provided.
+    pub capabilities: u64,
+
+    /// The physical address of the top-level page table installed by the bootloader.
+    ///
+    /// On x86_64, this is the value of `CR3`. On aarch64, this is the value of `TTBR1_EL1`.
+    /// On riscv64, this is the physical address encoded in `satp`.
+    pub page_table_root: u64,
+
+    /// An array of [`Framebuffer`]s.
+    ///
+    /// The first entry, if any, is the primary framebuffer.
+    pub framebuffers: *mut Framebuffer,
+    /// The number of [`Framebuffer`]s to which [`BootloaderResponse::framebuffers`] points.
+    pub framebuffer_count: usize,
+
+    /// Non-zero if UEFI runtime services may be called.
+    ///
+    /// The bootloader sets this once it has called `SetVirtualAddressMap` and mapped the
+    /// runtime services memory accordingly.
+    pub uefi_runtime_available: u8,
+
+    /// The virtual address of the kernel entry point.
+    ///
+    /// This is the ELF entry point of the kernel, which lies within the kernel image starting
+    /// at [`BootloaderResponse::kernel_virtual_address`] but need not equal it.
+    pub kernel_entry: *const core::ffi::c_void,
+
+    /// The signed difference between the address at which the kernel was loaded and the
+    /// address to which it was linked, or 0 if the bootloader did not randomize the load
+    /// address.
+    ///
+    /// Introduced in API version 10, see [`FieldMask`].
+    pub kaslr_slide: i64,
+
+    /// The physical address of the ACPI RSDP table, or 0 if not found.
+    ///
+    /// Introduced in API version 12, see [`FieldMask`].
+    pub rsdp_physical_address: u64,
+
+    /// A utf-8 string containing the name of the firmware vendor.
+    ///
+    /// This is the `FirmwareVendor` of the UEFI System Table, converted from UCS-2.
+    ///
+    /// Introduced in API version 13, see [`FieldMask`].
+    pub firmware_vendor: *const u8,
+    /// The length, in bytes, of [`BootloaderResponse::firmware_vendor`].
+    pub firmware_vendor_length: usize,
+
+    /// The physical address at which the kernel image was loaded.
+    ///
+    /// Introduced in API version 15, see [`FieldMask`].
+    pub kernel_physical_address: u64,
+    /// The size, in bytes, of the kernel image at
+    /// [`BootloaderResponse::kernel_physical_address`].
+    pub kernel_size: u64,
+
+    /// The first block of a linked list of [`ExtensionHeader`]s, or NULL if there are none.
+    ///
+    /// Introduced in API version 16, see [`FieldMask`].
+    pub extensions: *const ExtensionHeader,
+
+    /// The [`memory_map::checksum`] of the memory map, which allows corruption of the memory
+    /// map to be detected, see [`BootloaderResponse::audit()`].
+    ///
+    /// Introduced in API version 17, see [`FieldMask`].
+    pub memory_map_checksum: u64,
+}
+
+impl BootloaderResponse {
+    /// Returns the [`MemoryMapEntry`]s provided by the bootloader.
+    ///
+    /// Returns an empty slice without reading through
+    /// [`BootloaderResponse::memory_map_entries`] if
+    /// [`BootloaderResponse::memory_map_entry_count`] is 0.
+    ///
+    /// # Safety
+    /// If [`BootloaderResponse::memory_map_entry_count`] is not 0,
+    /// [`BootloaderResponse::memory_map_entries`] must point to that many valid
+    /// [`MemoryMapEntry`]s that remain valid and unmodified for the lifetime of `self`.
+    pub unsafe fn memory_map(&self) -> &[MemoryMapEntry] {
+        if self.memory_map_entry_count == 0 {
+            return &[];
+        }
+
+        // SAFETY: the caller guarantees that the memory map is valid for the lifetime of `self`.
+        unsafe { core::slice::from_raw_parts(self.memory_map_entries, self.memory_map_entry_count) }
+    }
+
+    /// Returns the [`ModuleEntry`]s provided by the bootloader.
+    ///
+    /// Returns an empty slice without reading through [`BootloaderResponse::module_entries`]
+    /// if [`BootloaderResponse::module_entry_count`] is 0.
+    ///
+    /// # Safety
+    /// If [`BootloaderResponse::module_entry_count`] is not 0,
+    /// [`BootloaderResponse::module_entries`] must point to that many valid [`ModuleEntry`]s
+    /// that remain valid and unmodified for the lifetime of `self`.
+    pub unsafe fn modules(&self) -> &[ModuleEntry] {
+        if self.module_entry_count == 0 {
+            return &[];
+        }
+
+        // SAFETY: the caller guarantees that the modules are valid for the lifetime of `self`.
+        unsafe { core::slice::from_raw_parts(self.module_entries, self.module_entry_count) }
+    }
+
+    /// Returns the [`MemoryMapEntry`]s provided by the bootloader, guarding against an
+    /// obviously corrupted [`BootloaderResponse::memory_map_entry_count`].
+    ///
+    /// # Errors
+    /// Returns [`ExcessiveCount`] without reading through
+    /// [`BootloaderResponse::memory_map_entries`] if
+    /// [`BootloaderResponse::memory_map_entry_count`] exceeds [`MAX_REASONABLE_ENTRIES`].
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::memory_map()`] apply if the count is
+    /// within bounds.
+    pub unsafe fn try_memory_map(&self) -> Result<&[MemoryMapEntry], ExcessiveCount> {
+        if self.memory_map_entry_count > MAX_REASONABLE_ENTRIES {
+            return Err(ExcessiveCount {
+                count: self.memory_map_entry_count,
+            });
+        }
+
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+        Ok(unsafe { self.memory_map() })
+    }
+
+    /// Returns the [`ModuleEntry`]s provided by the bootloader, guarding against an obviously
+    /// corrupted [`BootloaderResponse::module_entry_count`].
+    ///
+    /// # Errors
+    /// Returns [`ExcessiveCount`] without reading through
+    /// [`BootloaderResponse::module_entries`] if [`BootloaderResponse::module_entry_count`]
+    /// exceeds [`MAX_REASONABLE_ENTRIES`].
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules()`] apply if the count is within
+    /// bounds.
+    pub unsafe fn try_modules(&self) -> Result<&[ModuleEntry], ExcessiveCount> {
+        if self.module_entry_count > MAX_REASONABLE_ENTRIES {
+            return Err(ExcessiveCount {
+                count: self.module_entry_count,
+            });
+        }
+
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        Ok(unsafe { self.modules() })
+    }
+
+    /// Returns the virtual address of the base of the kernel.
+    pub fn kernel_virtual_address(&self) -> VirtAddr {
+        VirtAddr::from_ptr(self.kernel_virtual_address)
+    }
+
+    /// Returns the virtual address of the kernel entry point.
+    pub fn kernel_entry(&self) -> VirtAddr {
+        VirtAddr::from_ptr(self.kernel_entry)
+    }
+
+    /// Returns the signed difference between the address at which the kernel was loaded and
+    /// the address to which it was linked.
+    ///
+    /// Returns 0 if [`FieldMask::KASLR_SLIDE`] is not available.
+    pub fn kaslr_slide(&self) -> i64 {
+        if !self.has_fields(FieldMask::KASLR_SLIDE) {
+            return 0;
+        }
+
+        self.kaslr_slide
+    }
+
+    /// Returns the physical address of the ACPI RSDP table.
+    ///
+    /// Returns [`None`] if the RSDP was not found or [`FieldMask::RSDP_PHYSICAL_ADDRESS`] is
+    /// not available.
+    pub fn rsdp_physical(&self) -> Option<u64> {
+        if !self.has_fields(FieldMask::RSDP_PHYSICAL_ADDRESS) || self.rsdp_table_ptr.is_null() {
+            return None;
+        }
+
+        Some(self.rsdp_physical_address)
+    }
+
+    /// Returns the physical address range occupied by the kernel image.
+    ///
+    /// Returns [`None`] if the kernel image is empty or [`FieldMask::KERNEL_PHYSICAL`] is not
+    /// available.
+    pub fn kernel_physical_range(&self) -> Option<core::ops::Range<u64>> {
+        if !self.has_fields(FieldMask::KERNEL_PHYSICAL) || self.kernel_size == 0 {
+            return None;
+        }
+
+        let start = self.kernel_physical_address;
+        Some(start..start.saturating_add(self.kernel_size))
+    }
+
+    /// Translates `virt`, an address within the kernel image, to the physical address at which
+    /// it was loaded, using the offset between [`BootloaderResponse::kernel_virtual_address`]
+    /// and [`BootloaderResponse::kernel_physical_address`].
+    ///
+    /// Returns [`None`] if [`BootloaderResponse::kernel_physical_range()`] is [`None`] or `virt`
+    /// does not lie within the kernel image.
+    pub fn kernel_virt_to_phys(&self, virt: VirtAddr) -> Option<PhysAddr> {
+        let range = self.kernel_physical_range()?;
+        let offset = virt
+            .as_usize()
+            .checked_sub(self.kernel_virtual_address().as_usize())?;
+        let phys = range.start.checked_add(offset as u64)?;
+        range.contains(&phys).then_some(PhysAddr::new(phys))
+    }
+
+    /// Translates `phys`, an address within the kernel image, to the virtual address to which
+    /// it is mapped, the inverse of [`BootloaderResponse::kernel_virt_to_phys`].
+    ///
+    /// Returns [`None`] if [`BootloaderResponse::kernel_physical_range()`] is [`None`] or `phys`
+    /// does not lie within the kernel image.
+    pub fn kernel_phys_to_virt(&self, phys: PhysAddr) -> Option<VirtAddr> {
+        let range = self.kernel_physical_range()?;
+        if !range.contains(&phys.as_u64()) {
+            return None;
+        }
+
+        let offset = usize::try_from(phys.as_u64() - range.start).ok()?;
+        let virt = self
+            .kernel_virtual_address()
+            .as_usize()
+            .checked_add(offset)?;
+        Some(VirtAddr::new(virt))
+    }
+
+    /// Returns [`BootloaderResponse::memory_map_checksum`], or [`None`] if
+    /// [`FieldMask::MEMORY_MAP_CHECKSUM`] is not available.
+    pub fn memory_map_checksum(&self) -> Option<u64> {
+        if !self.has_fields(FieldMask::MEMORY_MAP_CHECKSUM) {
+            return None;
+        }
+
+        Some(self.memory_map_checksum)
+    }
+
+    /// Returns an iterator over the [`ExtensionHeader`]s in the list to which
+    /// [`BootloaderResponse::extensions`] points, in list order.
+    ///
+    /// Returns an empty iterator if [`FieldMask::EXTENSIONS`] is not available.
+    ///
+    /// # Safety
+    /// [`BootloaderResponse::extensions`] and the [`ExtensionHeader::next`] of every block in
+    /// the list must either be NULL or point to an [`ExtensionHeader`] that remains valid and
+    /// unmodified for the lifetime of `self`.
+    pub unsafe fn extensions(&self) -> impl Iterator<Item = &ExtensionHeader> {
+        let head = if self.has_fields(FieldMask::EXTENSIONS) {
+            self.extensions
+        } else {
+            core::ptr::null()
+        };
+
+        // SAFETY: the caller guarantees that `head` is NULL or points to a valid header.
+        core::iter::successors(unsafe { head.as_ref() }, |header| {
+            // SAFETY: the caller guarantees that every `next` is NULL or points to a valid
+            // header.
+            unsafe { header.next.as_ref() }
+        })
+    }
+
+    /// Returns the firmware table pointers of this [`BootloaderResponse`], with NULL pointers
+    /// as [`None`].
+    pub fn firmware_tables(&self) -> FirmwareTables {
+        fn non_null(ptr: *const core::ffi::c_void) -> Option<*const core::ffi::c_void> {
+            (!ptr.is_null()).then_some(ptr)
+        }
+
+        FirmwareTables {
+            rsdp: non_null(self.rsdp_table_ptr),
+            sm_bios_entry_32: non_null(self.sm_bios_entry_32),
+            sm_bios_entry_64: non_null(self.sm_bios_entry_64),
+            uefi_system_table: non_null(self.uefi_system_table_ptr),
+        }
+    }
+
+    /// Returns the offset of the higher half direct mapped memory.
+    pub const fn direct_map(&self) -> DirectMapOffset {
+        DirectMapOffset(self.direct_map)
+    }
+
+    /// Returns the number of paging levels active upon kernel entry, or 0 if unknown.
+    pub const fn paging_levels(&self) -> u8 {
+        self.paging_levels
+    }
+
+    /// Returns `true` if the bootloader advertised every `CAP_*` bit in `cap`.
+    pub const fn has_capability(&self, cap: u64) -> bool {
+        self.capabilities & cap == cap
+    }
+
+    /// Returns the name of the loading bootloader.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the name is not valid utf-8.
+    ///
+    /// # Safety
+    /// [`BootloaderResponse::bootloader_name`] must point to
+    /// [`BootloaderResponse::bootloader_name_length`] readable bytes that remain valid and
+    /// unmodified for the lifetime of `self`.
+    pub unsafe fn bootloader_name(&self) -> Result<&str, Utf8Error> {
+        // SAFETY: the caller guarantees that the name is valid for the lifetime of `self`.
+        unsafe { str_from_raw_parts(self.bootloader_name, self.bootloader_name_length) }
+    }
+
+    /// Returns an owned copy of the name of the loading bootloader.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the name is not valid utf-8.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::bootloader_name()`] apply.
+    #[cfg(feature = "alloc")]
+    pub unsafe fn bootloader_name_owned(&self) -> Result<String, Utf8Error> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::bootloader_name()`.
+        unsafe { self.bootloader_name() }.map(String::from)
+    }
+
+    /// Returns the version of the loading bootloader.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the version is not valid utf-8.
+    ///
+    /// # Safety
+    /// [`BootloaderResponse::bootloader_version`] must point to
+    /// [`BootloaderResponse::bootloader_version_length`] readable bytes that remain valid and
+    /// unmodified for the lifetime of `self`.
+    pub unsafe fn bootloader_version(&self) -> Result<&str, Utf8Error> {
+        // SAFETY: the caller guarantees that the version is valid for the lifetime of `self`.
+        unsafe { str_from_raw_parts(self.bootloader_version, self.bootloader_version_length) }
+    }
+
+    /// Returns an owned copy of the version of the loading bootloader.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the version is not valid utf-8.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::bootloader_version()`] apply.
+    #[cfg(feature = "alloc")]
+    pub unsafe fn bootloader_version_owned(&self) -> Result<String, Utf8Error> {
+        // SAFETY: the caller upholds the requirements of
+        // `BootloaderResponse::bootloader_version()`.
+        unsafe { self.bootloader_version() }.map(String::from)
+    }
+
+    /// Returns the [`Framebuffer`]s provided by the bootloader.
+    ///
+    /// Returns an empty slice without reading through [`BootloaderResponse::framebuffers`] if
+    /// [`BootloaderResponse::framebuffer_count`] is 0.
+    ///
+    /// # Safety
+    /// If [`BootloaderResponse::framebuffer_count`] is not 0,
+    /// [`BootloaderResponse::framebuffers`] must point to that many valid [`Framebuffer`]s
+    /// that remain valid and unmodified for the lifetime of `self`.
+    pub unsafe fn framebuffers(&self) -> &[Framebuffer] {
+        if self.framebuffer_count == 0 {
+            return &[];
+        }
+
+        // SAFETY: the caller guarantees that the framebuffers are valid for the lifetime of
+        // `self`.
+        unsafe { core::slice::from_raw_parts(self.framebuffers, self.framebuffer_count) }
+    }
+
+    /// Returns the primary [`Framebuffer`], if the bootloader provided any.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::framebuffers()`] apply.
+    pub unsafe fn primary_framebuffer(&self) -> Option<&Framebuffer> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::framebuffers()`.
+        unsafe { self.framebuffers() }.first()
+    }
+
+    /// Returns `true` if UEFI runtime services, such as `GetTime` and `ResetSystem`, may be
+    /// called.
+    pub const fn uefi_runtime_available(&self) -> bool {
+        self.uefi_runtime_available != 0
+    }
+
+    /// Returns the physical address of the top-level page table installed by the bootloader.
+    pub const fn page_table_root(&self) -> u64 {
+        self.page_table_root
+    }
+
+    /// Returns the [`PROTOCOL_REVISION`] the loading bootloader was built against.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the revision is not valid utf-8.
+    ///
+    /// # Safety
+    /// [`BootloaderResponse::protocol_revision`] must point to
+    /// [`BootloaderResponse::protocol_revision_length`] readable bytes that remain valid and
+    /// unmodified for the lifetime of `self`.
+    pub unsafe fn protocol_revision(&self) -> Result<&str, Utf8Error> {
+        // SAFETY: the caller guarantees that the revision is valid for the lifetime of `self`.
+        unsafe { str_from_raw_parts(self.protocol_revision, self.protocol_revision_length) }
+    }
+
+    /// Returns the name of the firmware vendor.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the name is not valid utf-8.
+    ///
+    /// Returns an empty string if [`FieldMask::FIRMWARE_VENDOR`] is not available.
+    ///
+    /// # Safety
+    /// [`BootloaderResponse::firmware_vendor`] must point to
+    /// [`BootloaderResponse::firmware_vendor_length`] readable bytes that remain valid and
+    /// unmodified for the lifetime of `self`.
+    pub unsafe fn firmware_vendor(&self) -> Result<&str, Utf8Error> {
+        if !self.has_fields(FieldMask::FIRMWARE_VENDOR) {
+            return Ok("");
+        }
+
+        // SAFETY: the caller guarantees that the vendor is valid for the lifetime of `self`.
+        unsafe { str_from_raw_parts(self.firmware_vendor, self.firmware_vendor_length) }
+    }
+
+    /// Returns the version of the provided UEFI memory map descriptors.
+    pub const fn uefi_memory_map_descriptor_version(&self) -> UefiDescriptorVersion {
+        UefiDescriptorVersion(self.uefi_memory_map_descriptor_version)
+    }
+
+    /// Returns an iterator over the descriptors of the UEFI memory map.
+    ///
+    /// Returns [`None`] if the descriptor version is not one this crate knows how to
+    /// interpret, since the layout of the descriptors may differ between versions.
+    ///
+    /// # Safety
+    /// [`BootloaderResponse::uefi_memory_map`] must either be NULL or point to
+    /// [`BootloaderResponse::uefi_memory_map_size`] readable bytes that remain valid and
+    /// unmodified for the lifetime of `self`.
+    pub unsafe fn uefi_memory_map_descriptors(&self) -> Option<UefiMemoryDescriptors<'_>> {
+        let map = if self.uefi_memory_map.is_null() || self.uefi_memory_map_size == 0 {
+            &[]
+        } else {
+            // SAFETY: the caller guarantees that the UEFI memory map is valid for the lifetime
+            // of `self`.
+            unsafe {
+                core::slice::from_raw_parts(
+                    self.uefi_memory_map.cast::<u8>(),
+                    self.uefi_memory_map_size,
+                )
+            }
+        };
+
+        UefiMemoryDescriptors::new(
+            map,
+            self.uefi_memory_map_descriptor_size,
+            self.uefi_memory_map_descriptor_version(),
+        )
+    }
+}
+
+/// Constructs a [`str`] from `length` bytes starting at `ptr`, returning an empty string if
+/// `length` is 0.
+///
+/// # Safety
+/// If `length` is not 0, `ptr` must point to `length` readable bytes that remain valid and
+/// unmodified for the lifetime `'a`.
+unsafe fn str_from_raw_parts<'a>(ptr: *const u8, length: usize) -> Result<&'a str, Utf8Error> {
+    if length == 0 {
+        return Ok("");
+    }
+
+    // SAFETY: the caller guarantees that `ptr` is valid for `length` bytes.
+    core::str::from_utf8(unsafe { core::slice::from_raw_parts(ptr, length) })
+}
+
+/// A descriptor of a linear framebuffer set up by the bootloader.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Framebuffer {
+    /// The virtual address of the first pixel of the framebuffer.
+    pub address: *mut u8,
+    /// The width, in pixels, of the framebuffer.
+    pub width: u64,
+    /// The height, in pixels, of the framebuffer.
+    pub height: u64,
+    /// The number of bytes between the start of one row of pixels and the next.
+    pub pitch: u64,
+    /// The number of bits occupied by each pixel.
+    pub bits_per_pixel: u16,
+    /// The number of bits of the red channel.
+    pub red_mask_size: u8,
+    /// The bit offset of the red channel within a pixel.
+    pub red_mask_shift: u8,
+    /// The number of bits of the green channel.
+    pub green_mask_size: u8,
+    /// The bit offset of the green channel within a pixel.
+    pub green_mask_shift: u8,
+    /// The number of bits of the blue channel.
+    pub blue_mask_size: u8,
+    /// The bit offset of the blue channel within a pixel.
+    pub blue_mask_shift: u8,
+}
+
+impl Framebuffer {
+    /// Returns the [`PixelFormat`] described by the channel masks of this [`Framebuffer`].
+    pub const fn pixel_format(&self) -> PixelFormat {
+        match (
+            self.bits_per_pixel,
+            (self.red_mask_size, self.red_mask_shift),
+            (self.green_mask_size, self.green_mask_shift),
+            (self.blue_mask_size, self.blue_mask_shift),
+        ) {
+            (0, ..) | (_, (0, _), (0, _), (0, _)) => PixelFormat::Unknown,
+            (32, (8, 0), (8, 8), (8, 16)) => PixelFormat::Rgb,
+            (32, (8, 16), (8, 8), (8, 0)) => PixelFormat::Bgr,
+            _ => PixelFormat::Bitmask {
+                red_mask_size: self.red_mask_size,
+                red_mask_shift: self.red_mask_shift,
+                green_mask_size: self.green_mask_size,
+                green_mask_shift: self.green_mask_shift,
+                blue_mask_size: self.blue_mask_size,
+                blue_mask_shift: self.blue_mask_shift,
+            },
+        }
+    }
+}
+
+/// The layout of the pixels of a [`Framebuffer`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PixelFormat {
+    /// 32-bit pixels with 8-bit red, green, and blue channels, red in the lowest byte.
+    Rgb,
+    /// 32-bit pixels with 8-bit blue, green, and red channels, blue in the lowest byte.
+    Bgr,
+    /// Pixels in some other layout, described by their channel masks.
+    Bitmask {
+        /// The number of bits of the red channel.
+        red_mask_size: u8,
+        /// The bit offset of the red channel within a pixel.
+        red_mask_shift: u8,
+        /// The number of bits of the green channel.
+        green_mask_size: u8,
+        /// The bit offset of the green channel within a pixel.
+        green_mask_shift: u8,
+        /// The number of bits of the blue channel.
+        blue_mask_size: u8,
+        /// The bit offset of the blue channel within a pixel.
+        blue_mask_shift: u8,
+    },
+    /// The [`Framebuffer`] does not describe its pixels.
+    Unknown,
+}
+
+/// The header of a block in the list of extensions to which
+/// [`BootloaderResponse::extensions`] points.
+///
+/// The data of an extension immediately follows its header, laid out according to its
+/// [`ExtensionHeader::kind`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExtensionHeader {
+    /// The next block in the list, or NULL if this is the last one.
+    pub next: *const ExtensionHeader,
+    /// The kind of the extension, which determines the layout of its data.
+    pub kind: u64,
+    /// The size, in bytes, of the extension, including this header.
+    pub size: u64,
+}
+
+/// The firmware table pointers of a [`BootloaderResponse`], as returned by
+/// [`BootloaderResponse::firmware_tables()`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FirmwareTables {
+    /// The value of [`BootloaderResponse::rsdp_table_ptr`].
+    pub rsdp: Option<*const core::ffi::c_void>,
+    /// The value of [`BootloaderResponse::sm_bios_entry_32`].
+    pub sm_bios_entry_32: Option<*const core::ffi::c_void>,
+    /// The value of [`BootloaderResponse::sm_bios_entry_64`].
+    pub sm_bios_entry_64: Option<*const core::ffi::c_void>,
+    /// The value of [`BootloaderResponse::uefi_system_table_ptr`].
+    pub uefi_system_table: Option<*const core::ffi::c_void>,
+}
+
+impl core::fmt::Display for FirmwareTables {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let tables = [
+            ("rsdp", self.rsdp),
+            ("smbios 32", self.sm_bios_entry_32),
+            ("smbios 64", self.sm_bios_entry_64),
+            ("uefi system table", self.uefi_system_table),
+        ];
+
+        let mut present = tables.iter().filter(|(_, ptr)| ptr.is_some());
+        let Some((name, _)) = present.next() else {
+            return f.write_str("no firmware tables");
+        };
+        f.write_str(name)?;
+        for (name, _) in present {
+            write!(f, ", {name}")?;
+        }
+        Ok(())
+    }
 }
 
 /// A descriptor of a memory region.
@@ -100,6 +876,27 @@ pub struct MemoryMapEntry {
     pub size: u64,
 }
 
+impl MemoryMapEntry {
+    /// Returns the physical address of the base of the region.
+    pub const fn base_addr(&self) -> PhysAddr {
+        PhysAddr::new(self.base)
+    }
+
+    /// Returns the physical address one past the end of the region, saturating at the top of
+    /// the physical address space.
+    pub const fn end_addr(&self) -> PhysAddr {
+        PhysAddr::new(self.base.saturating_add(self.size))
+    }
+
+    /// Returns the base of the region, for use as the key of the entry in a map.
+    ///
+    /// Since the entries of a valid memory map never overlap, no two of them share a base, so
+    /// this is a stable key. See [`RegionKey`](memory_map::RegionKey) for a typed equivalent.
+    pub const fn base_key(&self) -> u64 {
+        self.base
+    }
+}
+
 /// The kind of a memory region.
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -134,6 +931,26 @@ impl MemoryMapEntryKind {
     pub const MODULE: Self = Self(8);
 }
 
+// SAFETY: `MemoryMapEntry` is `repr(C)` and consists of three 8 byte aligned, 8 byte fields,
+// so it has no padding, and all zeroes is a valid value for each of them.
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for MemoryMapEntry {}
+
+// SAFETY: `MemoryMapEntry` is `repr(C)` with no padding, and every field is `Pod`, so every
+// bit pattern is a valid value.
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for MemoryMapEntry {}
+
+// SAFETY: `MemoryMapEntryKind` is `repr(transparent)` over a `u64`, for which all zeroes is a
+// valid value.
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for MemoryMapEntryKind {}
+
+// SAFETY: `MemoryMapEntryKind` is `repr(transparent)` over a `u64`, for which every bit
+// pattern is valid, and unknown kinds are permitted.
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for MemoryMapEntryKind {}
+
 /// A descriptor of a module loaded at boot time.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -149,4 +966,701 @@ pub struct ModuleEntry {
     pub address: *const u8,
     /// The size, in bytes, of the loaded module.
     pub size: usize,
+
+    /// A bitfield of the `MODULE_*` flags describing how the module was loaded.
+    ///
+    /// A module with neither flag set is read-only and not executable. The bits from
+    /// [`MODULE_KIND_SHIFT`] upwards hold the [`ModuleKind`] of the module.
+    pub flags: u64,
+}
+
+/// The [`ModuleEntry`] was loaded into writable memory.
+pub const MODULE_WRITABLE: u64 = 1 << 0;
+/// The [`ModuleEntry`] was loaded into executable memory.
+pub const MODULE_EXECUTABLE: u64 = 1 << 1;
+/// The position of the lowest bit of the [`ModuleKind`] stored in [`ModuleEntry::flags`].
+pub const MODULE_KIND_SHIFT: u32 = 32;
+
+/// The kind of a loaded module.
+///
+/// Introduced in API version 14, see [`FieldMask`]. Earlier bootloaders leave the bits of
+/// [`ModuleEntry::flags`] that hold it clear, so their modules are all
+/// [`ModuleKind::UNKNOWN`].
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ModuleKind(u32);
+
+impl ModuleKind {
+    /// The kind of the module was not specified.
+    pub const UNKNOWN: Self = Self(0);
+    /// The module is an initial ramdisk.
+    pub const INITRD: Self = Self(1);
+    /// The module is a processor microcode update.
+    pub const MICROCODE: Self = Self(2);
+    /// The module is a flattened devicetree blob.
+    pub const DEVICE_TREE: Self = Self(3);
+
+    /// Returns the bits of [`ModuleEntry::flags`] that mark a module as this
+    /// [`ModuleKind`].
+    pub const fn to_flags(self) -> u64 {
+        (self.0 as u64) << MODULE_KIND_SHIFT
+    }
+}
+
+impl ModuleEntry {
+    /// Returns the name of the loaded module.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the name is not valid utf-8.
+    ///
+    /// # Safety
+    /// [`ModuleEntry::name`] must point to [`ModuleEntry::name_length`] readable bytes that
+    /// remain valid and unmodified for the lifetime of `self`.
+    pub unsafe fn name(&self) -> Result<&str, Utf8Error> {
+        // SAFETY: the caller guarantees that the name is valid for the lifetime of `self`.
+        unsafe { str_from_raw_parts(self.name, self.name_length) }
+    }
+
+    /// Returns `true` if the module was loaded into writable memory.
+    pub const fn is_writable(&self) -> bool {
+        self.flags & MODULE_WRITABLE != 0
+    }
+
+    /// Returns `true` if the module was loaded into executable memory.
+    pub const fn is_executable(&self) -> bool {
+        self.flags & MODULE_EXECUTABLE != 0
+    }
+
+    /// Returns the [`ModuleKind`] stored in [`ModuleEntry::flags`].
+    ///
+    /// This does not account for the API version of the response, use
+    /// [`BootloaderResponse::module_kind()`] for that.
+    pub const fn kind(&self) -> ModuleKind {
+        ModuleKind((self.flags >> MODULE_KIND_SHIFT) as u32)
+    }
+
+    /// Returns the data of the loaded module.
+    ///
+    /// Returns an empty slice without reading through [`ModuleEntry::address`] if
+    /// [`ModuleEntry::size`] is 0.
+    ///
+    /// # Safety
+    /// If [`ModuleEntry::size`] is not 0, [`ModuleEntry::address`] must point to that many
+    /// readable bytes that remain valid and unmodified for the lifetime of `self`.
+    pub unsafe fn data(&self) -> &[u8] {
+        if self.size == 0 {
+            return &[];
+        }
+
+        // SAFETY: the caller guarantees that the module data is valid for the lifetime of
+        // `self`.
+        unsafe { core::slice::from_raw_parts(self.address, self.size) }
+    }
+
+    /// Returns an iterator over the data of the loaded module in 4096 byte chunks.
+    ///
+    /// The final chunk is shorter than 4096 bytes if [`ModuleEntry::size`] is not a multiple
+    /// of 4096.
+    ///
+    /// # Safety
+    /// The same requirements as [`ModuleEntry::data`] apply.
+    pub unsafe fn data_pages(&self) -> impl Iterator<Item = &[u8]> {
+        // SAFETY: the caller upholds the requirements of `ModuleEntry::data()`.
+        unsafe { self.data() }.chunks(4096)
+    }
+}
+
+/// Folds the size, alignment, and listed field offsets of `$ty` into `$hash`.
+macro_rules! hash_layout {
+    ($hash:ident, $ty:ty { $($field:ident),* $(,)? }) => {
+        $hash = fnv1a_u64($hash, core::mem::size_of::<$ty>() as u64);
+        $hash = fnv1a_u64($hash, core::mem::align_of::<$ty>() as u64);
+        $($hash = fnv1a_u64($hash, core::mem::offset_of!($ty, $field) as u64);)*
+    };
+}
+
+/// Computes [`LAYOUT_HASH`] from the size, alignment, and field offsets of every type shared
+/// between the bootloader and the kernel.
+pub const fn layout_hash() -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    hash_layout!(
+        hash,
+        BootloaderRequest {
+            signature,
+            api_version,
+            requested_features
+        }
+    );
+    hash_layout!(
+        hash,
+        BootloaderResponse {
+            signature,
+            api_version,
+            bootloader_name,
+            bootloader_name_length,
+            bootloader_version,
+            bootloader_version_length,
+            kernel_virtual_address,
+            direct_map,
+            memory_map_entries,
+            memory_map_entry_count,
+            sm_bios_entry_32,
+            sm_bios_entry_64,
+            rsdp_table_ptr,
+            uefi_system_table_ptr,
+            uefi_memory_map,
+            uefi_memory_map_size,
+            uefi_memory_map_descriptor_size,
+            uefi_memory_map_descriptor_version,
+            module_entries,
+            module_entry_count,
+            protocol_revision,
+            protocol_revision_length,
+            paging_levels,
+            capabilities,
+            page_table_root,
+            framebuffers,
+            framebuffer_count,
+            uefi_runtime_available,
+            kernel_entry,
+            kaslr_slide,
+            rsdp_physical_address,
+            firmware_vendor,
+            firmware_vendor_length,
+            kernel_physical_address,
+            kernel_size,
+            extensions,
+            memory_map_checksum,
+        }
+    );
+    hash_layout!(hash, ExtensionHeader { next, kind, size });
+    hash_layout!(
+        hash,
+        Framebuffer {
+            address,
+            width,
+            height,
+            pitch,
+            bits_per_pixel,
+            red_mask_size,
+            red_mask_shift,
+            green_mask_size,
+            green_mask_shift,
+            blue_mask_size,
+            blue_mask_shift,
+        }
+    );
+    hash_layout!(hash, MemoryMapEntry { kind, base, size });
+    hash_layout!(hash, MemoryMapEntryKind {});
+    hash_layout!(
+        hash,
+        ModuleEntry {
+            name,
+            name_length,
+            address,
+            size,
+            flags,
+        }
+    );
+    hash
+}
+
+/// The offset basis of the 64-bit FNV-1a hash.
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// Folds `bytes` into the 64-bit FNV-1a `hash`.
+pub(crate) const fn fnv1a_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let mut index = 0;
+    while index < bytes.len() {
+        hash ^= bytes[index] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        index += 1;
+    }
+    hash
+}
+
+/// Folds the little-endian bytes of `value` into the 64-bit FNV-1a `hash`.
+const fn fnv1a_u64(hash: u64, value: u64) -> u64 {
+    fnv1a_bytes(hash, &value.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr::NonNull;
+
+    use super::*;
+    #[cfg(feature = "bytemuck")]
+    use crate::test_util::entry;
+    use crate::test_util::{framebuffer, module, response};
+
+    #[test]
+    fn data_pages_ends_with_remainder() {
+        let data = [0xa5; 2 * 4096 + 100];
+        let module = module("module", data.as_ptr(), data.len());
+
+        // SAFETY: the module points to `data`, which outlives `module`.
+        let pages = unsafe { module.data_pages() }.collect::<std::vec::Vec<_>>();
+        assert_eq!(pages.len(), 3);
+        assert!(pages[..2].iter().all(|page| page.len() == 4096));
+        assert_eq!(pages[2].len(), 100);
+        assert!(pages
+            .iter()
+            .flat_map(|page| page.iter())
+            .all(|&byte| byte == 0xa5));
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn bytemuck_round_trip() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::MODULE, 0x1000, 0x2000),
+        ];
+
+        let bytes: &[u8] = bytemuck::cast_slice(&entries);
+        assert_eq!(bytes.len(), 2 * core::mem::size_of::<MemoryMapEntry>());
+        assert_eq!(bytes[24..32], 8u64.to_ne_bytes());
+        assert_eq!(bytemuck::cast_slice::<u8, MemoryMapEntry>(bytes), entries);
+        assert_eq!(
+            bytemuck::cast::<u64, MemoryMapEntryKind>(7),
+            MemoryMapEntryKind::KERNEL
+        );
+    }
+
+    #[test]
+    fn layout_hash_tracks_layout() {
+        #[repr(C)]
+        struct Before {
+            a: u64,
+            b: u32,
+        }
+
+        #[repr(C)]
+        struct After {
+            a: u64,
+            c: u32,
+            b: u32,
+        }
+
+        let (mut before, mut after) = (FNV_OFFSET_BASIS, FNV_OFFSET_BASIS);
+        hash_layout!(before, Before { a, b });
+        hash_layout!(after, After { a, b });
+        assert_ne!(before, after);
+
+        let mut again = FNV_OFFSET_BASIS;
+        hash_layout!(again, Before { a, b });
+        assert_eq!(before, again);
+
+        assert_eq!(layout_hash(), LAYOUT_HASH);
+    }
+
+    // Under `cargo miri test`, any read through the dangling pointers below is reported.
+
+    #[test]
+    fn empty_memory_map_is_not_read() {
+        let mut response = response();
+        response.memory_map_entries = NonNull::<MemoryMapEntry>::dangling().as_ptr();
+        response.memory_map_entry_count = 0;
+
+        // SAFETY: the count is 0, so the dangling pointer must never be read.
+        assert!(unsafe { response.memory_map() }.is_empty());
+    }
+
+    #[test]
+    fn empty_modules_are_not_read() {
+        let mut response = response();
+        response.module_entries = NonNull::<ModuleEntry>::dangling().as_ptr();
+        response.module_entry_count = 0;
+
+        // SAFETY: the count is 0, so the dangling pointer must never be read.
+        assert!(unsafe { response.modules() }.is_empty());
+    }
+
+    #[test]
+    fn has_capability_requires_every_bit() {
+        let mut response = response();
+        response.capabilities = CAP_RSDP | CAP_FRAMEBUFFER;
+
+        assert!(response.has_capability(CAP_RSDP));
+        assert!(response.has_capability(CAP_RSDP | CAP_FRAMEBUFFER));
+        assert!(!response.has_capability(CAP_RSDP | CAP_COMMAND_LINE));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn owned_strings_match_borrowed() {
+        let (name, version) = ("capora", "0.1.0");
+        let mut response = response();
+        response.bootloader_name = name.as_ptr();
+        response.bootloader_name_length = name.len();
+        response.bootloader_version = version.as_ptr();
+        response.bootloader_version_length = version.len();
+
+        // SAFETY: the strings point to `name` and `version`, which outlive `response`.
+        unsafe {
+            assert_eq!(
+                response.bootloader_name_owned().ok().as_deref(),
+                response.bootloader_name().ok()
+            );
+            assert_eq!(
+                response.bootloader_version_owned().ok().as_deref(),
+                response.bootloader_version().ok()
+            );
+            assert_eq!(response.bootloader_name_owned().ok().as_deref(), Some(name));
+        }
+    }
+
+    #[test]
+    fn api_version_is_supported() {
+        assert!(is_supported_version(API_VERSION));
+        assert!(is_supported_version(MIN_SUPPORTED_API_VERSION));
+        assert!(!is_supported_version(MIN_SUPPORTED_API_VERSION - 1));
+        assert!(!is_supported_version(MAX_SUPPORTED_API_VERSION + 1));
+        assert_eq!(BootloaderRequest::new().validate(), Ok(()));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn page_table_root_offset() {
+        assert_eq!(
+            core::mem::offset_of!(BootloaderResponse, page_table_root),
+            208
+        );
+        assert_eq!(
+            core::mem::offset_of!(BootloaderResponse, page_table_root),
+            core::mem::offset_of!(BootloaderResponse, capabilities) + 8
+        );
+    }
+
+    #[test]
+    fn module_flags() {
+        let mut module = module("module", core::ptr::null(), 0);
+        assert!(!module.is_writable() && !module.is_executable());
+
+        module.flags = MODULE_WRITABLE;
+        assert!(module.is_writable() && !module.is_executable());
+
+        module.flags = MODULE_WRITABLE | MODULE_EXECUTABLE;
+        assert!(module.is_writable() && module.is_executable());
+
+        module.flags = MODULE_EXECUTABLE;
+        assert!(!module.is_writable() && module.is_executable());
+    }
+
+    #[test]
+    fn framebuffer_counts() {
+        let mut framebuffers = [
+            framebuffer(0x1000 as *mut u8, 1024, 768),
+            framebuffer(0x2000 as *mut u8, 800, 600),
+        ];
+        let mut response = response();
+        response.framebuffers = framebuffers.as_mut_ptr();
+
+        for count in 0..=2 {
+            response.framebuffer_count = count;
+            // SAFETY: the framebuffers point to `framebuffers`, which outlives `response`.
+            unsafe {
+                assert_eq!(response.framebuffers(), &framebuffers[..count]);
+                assert_eq!(
+                    response.primary_framebuffer(),
+                    framebuffers[..count].first()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn uefi_runtime_flag() {
+        let mut response = response();
+        assert!(!response.uefi_runtime_available());
+
+        response.uefi_runtime_available = 1;
+        assert!(response.uefi_runtime_available());
+    }
+
+    #[test]
+    fn typed_addresses() {
+        let entry = MemoryMapEntry {
+            kind: MemoryMapEntryKind::KERNEL,
+            base: 0x20_0000,
+            size: 0x3000,
+        };
+        assert_eq!(entry.base_addr(), PhysAddr::new(0x20_0000));
+        assert_eq!(entry.end_addr(), PhysAddr::new(0x20_3000));
+
+        let mut response = response();
+        response.direct_map = 0xffff_8000_0000_0000;
+        response.kernel_virtual_address = 0xffff_ffff_8000_0000usize as *const _;
+        assert_eq!(
+            response.kernel_virtual_address(),
+            VirtAddr::new(0xffff_ffff_8000_0000)
+        );
+
+        let virt = entry.base_addr().to_virt(response.direct_map());
+        assert_eq!(virt.as_usize(), 0xffff_8000_0020_0000);
+        assert_eq!(virt.to_phys(response.direct_map()), entry.base_addr());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn kernel_entry_offset() {
+        assert_eq!(core::mem::offset_of!(BootloaderResponse, kernel_entry), 240);
+        assert_eq!(
+            core::mem::offset_of!(BootloaderResponse, kernel_entry),
+            core::mem::offset_of!(BootloaderResponse, uefi_runtime_available) + 8
+        );
+    }
+
+    #[test]
+    fn segment_filesz_matches_request() {
+        assert_eq!(segment_filesz(), BOOTLOADER_REQUEST_SIZE as u64);
+        assert_eq!(
+            BOOTLOADER_REQUEST_SIZE,
+            core::mem::size_of_val(&BootloaderRequest::new())
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn kaslr_slide_offset() {
+        assert_eq!(core::mem::offset_of!(BootloaderResponse, kaslr_slide), 248);
+        assert_eq!(
+            core::mem::offset_of!(BootloaderResponse, kaslr_slide),
+            core::mem::offset_of!(BootloaderResponse, kernel_entry) + 8
+        );
+    }
+
+    #[test]
+    fn kaslr_slide_is_gated() {
+        let mut response = response();
+        response.kaslr_slide = -0x20_0000;
+        assert_eq!(response.kaslr_slide(), -0x20_0000);
+
+        response.api_version = 9;
+        assert_eq!(response.kaslr_slide(), 0);
+    }
+
+    #[test]
+    fn pixel_format_from_masks() {
+        let mut framebuffer = framebuffer(core::ptr::null_mut(), 640, 480);
+        assert_eq!(framebuffer.pixel_format(), PixelFormat::Bgr);
+
+        framebuffer.red_mask_shift = 0;
+        framebuffer.blue_mask_shift = 16;
+        assert_eq!(framebuffer.pixel_format(), PixelFormat::Rgb);
+
+        framebuffer.bits_per_pixel = 16;
+        framebuffer.red_mask_size = 5;
+        framebuffer.green_mask_size = 6;
+        framebuffer.blue_mask_size = 5;
+        framebuffer.green_mask_shift = 5;
+        framebuffer.blue_mask_shift = 11;
+        assert_eq!(
+            framebuffer.pixel_format(),
+            PixelFormat::Bitmask {
+                red_mask_size: 5,
+                red_mask_shift: 0,
+                green_mask_size: 6,
+                green_mask_shift: 5,
+                blue_mask_size: 5,
+                blue_mask_shift: 11,
+            }
+        );
+
+        framebuffer.bits_per_pixel = 0;
+        assert_eq!(framebuffer.pixel_format(), PixelFormat::Unknown);
+    }
+
+    #[test]
+    fn absurd_counts_are_rejected() {
+        let mut response = response();
+        response.memory_map_entries = NonNull::dangling().as_ptr();
+        response.memory_map_entry_count = usize::MAX;
+        response.module_entries = NonNull::dangling().as_ptr();
+        response.module_entry_count = MAX_REASONABLE_ENTRIES + 1;
+
+        // SAFETY: both counts exceed `MAX_REASONABLE_ENTRIES`, so neither dangling pointer is
+        // read.
+        unsafe {
+            assert_eq!(
+                response.try_memory_map(),
+                Err(ExcessiveCount { count: usize::MAX })
+            );
+            assert_eq!(
+                response.try_modules().map(<[_]>::len),
+                Err(ExcessiveCount {
+                    count: MAX_REASONABLE_ENTRIES + 1
+                })
+            );
+        }
+
+        response.module_entry_count = 0;
+        // SAFETY: the module count is 0, so the dangling pointer is not read.
+        assert_eq!(unsafe { response.try_modules() }.map(<[_]>::len), Ok(0));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn rsdp_physical_address_offset() {
+        assert_eq!(
+            core::mem::offset_of!(BootloaderResponse, rsdp_physical_address),
+            256
+        );
+        assert_eq!(
+            core::mem::offset_of!(BootloaderResponse, rsdp_physical_address),
+            core::mem::offset_of!(BootloaderResponse, kaslr_slide) + 8
+        );
+    }
+
+    #[test]
+    fn rsdp_physical_requires_rsdp() {
+        let mut response = response();
+        response.rsdp_physical_address = 0xe_0000;
+        assert_eq!(response.rsdp_physical(), None);
+
+        response.rsdp_table_ptr = 0xffff_8000_000e_0000_usize as *const _;
+        assert_eq!(response.rsdp_physical(), Some(0xe_0000));
+
+        response.api_version = 11;
+        assert_eq!(response.rsdp_physical(), None);
+    }
+
+    #[test]
+    fn firmware_vendor_string() {
+        let vendor = "EDK II";
+        let mut response = response();
+        // SAFETY: the firmware vendor length is 0.
+        assert_eq!(unsafe { response.firmware_vendor() }, Ok(""));
+
+        response.firmware_vendor = vendor.as_ptr();
+        response.firmware_vendor_length = vendor.len();
+        // SAFETY: the firmware vendor points to a string literal.
+        assert_eq!(unsafe { response.firmware_vendor() }, Ok("EDK II"));
+
+        response.api_version = 12;
+        // SAFETY: the firmware vendor points to a string literal.
+        assert_eq!(unsafe { response.firmware_vendor() }, Ok(""));
+    }
+
+    crate::require_api_version!(MIN_SUPPORTED_API_VERSION);
+    crate::require_api_version!(API_VERSION);
+
+    #[test]
+    fn require_version_compares_against_api_version() {
+        assert!(require_version(0));
+        assert!(require_version(API_VERSION));
+        assert!(!require_version(API_VERSION + 1));
+    }
+
+    #[test]
+    fn firmware_tables_skip_null_pointers() {
+        let mut response = response();
+        assert_eq!(
+            std::format!("{}", response.firmware_tables()),
+            "no firmware tables"
+        );
+
+        let (rsdp, smbios) = (0x1000 as *const _, 0x2000 as *const _);
+        response.rsdp_table_ptr = rsdp;
+        response.sm_bios_entry_64 = smbios;
+        let tables = response.firmware_tables();
+        assert_eq!(
+            tables,
+            FirmwareTables {
+                rsdp: Some(rsdp),
+                sm_bios_entry_32: None,
+                sm_bios_entry_64: Some(smbios),
+                uefi_system_table: None,
+            }
+        );
+        assert_eq!(std::format!("{tables}"), "rsdp, smbios 64");
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn kernel_physical_offsets() {
+        assert_eq!(
+            core::mem::offset_of!(BootloaderResponse, kernel_physical_address),
+            280
+        );
+        assert_eq!(core::mem::offset_of!(BootloaderResponse, kernel_size), 288);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn extensions_offset() {
+        assert_eq!(core::mem::offset_of!(BootloaderResponse, extensions), 296);
+        assert_eq!(
+            core::mem::offset_of!(BootloaderResponse, memory_map_checksum),
+            304
+        );
+    }
+
+    #[test]
+    fn extensions_gating() {
+        let extension = ExtensionHeader {
+            next: core::ptr::null(),
+            kind: 7,
+            size: core::mem::size_of::<ExtensionHeader>() as u64,
+        };
+        let mut response = response();
+        response.extensions = &extension;
+        // SAFETY: the extensions point to `extension`, which outlives `response`.
+        assert_eq!(unsafe { response.extensions() }.count(), 1);
+
+        response.api_version = 15;
+        // SAFETY: the extensions are not available, so they are never read.
+        assert_eq!(unsafe { response.extensions() }.count(), 0);
+    }
+
+    #[test]
+    fn kernel_physical_range_gating() {
+        let mut response = response();
+        response.kernel_physical_address = 0x10_0000;
+        assert_eq!(response.kernel_physical_range(), None);
+
+        response.kernel_size = 0x4000;
+        assert_eq!(response.kernel_physical_range(), Some(0x10_0000..0x10_4000));
+
+        response.kernel_physical_address = u64::MAX - 0x1000;
+        assert_eq!(
+            response.kernel_physical_range(),
+            Some(u64::MAX - 0x1000..u64::MAX)
+        );
+
+        response.api_version = 14;
+        assert_eq!(response.kernel_physical_range(), None);
+    }
+
+    #[test]
+    fn kernel_address_translation() {
+        let mut response = response();
+        response.kernel_virtual_address = 0xffff_ffff_8000_0000 as *const _;
+        response.kernel_physical_address = 0x10_0000;
+        let inside = VirtAddr::new(0xffff_ffff_8000_1234);
+        assert_eq!(response.kernel_virt_to_phys(inside), None);
+
+        response.kernel_size = 0x4000;
+        assert_eq!(
+            response.kernel_virt_to_phys(inside),
+            Some(PhysAddr::new(0x10_1234))
+        );
+        assert_eq!(
+            response.kernel_phys_to_virt(PhysAddr::new(0x10_1234)),
+            Some(inside)
+        );
+
+        let below = VirtAddr::new(0xffff_ffff_7fff_ffff);
+        let past_end = VirtAddr::new(0xffff_ffff_8000_4000);
+        assert_eq!(response.kernel_virt_to_phys(below), None);
+        assert_eq!(response.kernel_virt_to_phys(past_end), None);
+        assert_eq!(response.kernel_phys_to_virt(PhysAddr::new(0xf_ffff)), None);
+        assert_eq!(response.kernel_phys_to_virt(PhysAddr::new(0x10_4000)), None);
+
+        response.api_version = 14;
+        assert_eq!(response.kernel_virt_to_phys(inside), None);
+        assert_eq!(response.kernel_phys_to_virt(PhysAddr::new(0x10_1234)), None);
+    }
 }
@@ -0,0 +1,1913 @@
+//! Helpers for inspecting a memory map made up of [`MemoryMapEntry`]s.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{
+    util::{align_down, align_up},
+    MemoryMapEntry, MemoryMapEntryKind,
+};
+
+/// The size, in bytes, of a physical frame.
+pub(crate) const FRAME_SIZE: u64 = 4096;
+
+/// Returns the address one past the highest byte described by `entries`.
+///
+/// Returns 0 if `entries` is empty.
+pub fn highest_address(entries: &[MemoryMapEntry]) -> u64 {
+    entries
+        .iter()
+        .map(|entry| entry.base.saturating_add(entry.size))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the range from the lowest base to the highest end of the entries in `entries`.
+///
+/// This does not require `entries` to be sorted. Returns [`None`] if `entries` is empty.
+pub fn total_span(entries: &[MemoryMapEntry]) -> Option<core::ops::Range<u64>> {
+    let start = entries.iter().map(|entry| entry.base).min()?;
+    Some(start..highest_address(entries))
+}
+
+/// Returns the widest range between consecutive entries of `entries` that no entry covers.
+///
+/// `entries` must be sorted by base address. If several gaps are equally wide, the lowest is
+/// returned. Returns [`None`] if there are no gaps.
+pub fn largest_gap(entries: &[MemoryMapEntry]) -> Option<core::ops::Range<u64>> {
+    let mut largest: Option<core::ops::Range<u64>> = None;
+    let mut end = entries.first()?.base;
+    for entry in entries {
+        if entry.base > end
+            && largest
+                .as_ref()
+                .is_none_or(|largest| entry.base - end > largest.end - largest.start)
+        {
+            largest = Some(end..entry.base);
+        }
+        end = end.max(entry.base.saturating_add(entry.size));
+    }
+    largest
+}
+
+/// Returns the entry in `entries` that contains `address`, if any.
+///
+/// This performs a linear search and so does not require `entries` to be sorted.
+pub fn region_containing(entries: &[MemoryMapEntry], address: u64) -> Option<&MemoryMapEntry> {
+    entries.iter().find(|entry| contains(entry, address))
+}
+
+/// Returns the highest entry in `entries` that ends at or below `address`, if any.
+///
+/// This performs a binary search, and so requires `entries` to be sorted and
+/// non-overlapping.
+pub fn region_before(entries: &[MemoryMapEntry], address: u64) -> Option<&MemoryMapEntry> {
+    let index = entries.partition_point(|entry| entry.end_addr().as_u64() <= address);
+    index.checked_sub(1).map(|index| &entries[index])
+}
+
+/// Returns the lowest entry in `entries` that starts at or above `address`, if any.
+///
+/// This performs a binary search, and so requires `entries` to be sorted and
+/// non-overlapping.
+pub fn region_after(entries: &[MemoryMapEntry], address: u64) -> Option<&MemoryMapEntry> {
+    let index = entries.partition_point(|entry| entry.base < address);
+    entries.get(index)
+}
+
+/// Returns the number of physical frames needed to cover every address below
+/// [`highest_address`].
+pub fn physical_frame_count(entries: &[MemoryMapEntry]) -> u64 {
+    highest_address(entries).div_ceil(FRAME_SIZE)
+}
+
+/// Returns the number of bytes required by a bitmap that tracks every frame counted by
+/// [`physical_frame_count`] with a single bit.
+pub fn physical_bitmap_bytes(entries: &[MemoryMapEntry]) -> u64 {
+    physical_frame_count(entries).div_ceil(8)
+}
+
+/// Returns an iterator over `entries` paired with their indices.
+///
+/// This is the canonical way of referring to entries: every index reported by a
+/// [`MemoryMapError`] matches the position yielded by this iterator.
+pub fn enumerate_memory_map(
+    entries: &[MemoryMapEntry],
+) -> impl Iterator<Item = (usize, &MemoryMapEntry)> {
+    entries.iter().enumerate()
+}
+
+/// Various errors that can occur when validating a memory map.
+///
+/// Each variant carries the index of the offending entry, as yielded by
+/// [`enumerate_memory_map`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemoryMapError {
+    /// The entry at `index` has a base or size that is not 4096 byte aligned.
+    Unaligned {
+        /// The index of the offending entry.
+        index: usize,
+    },
+    /// The entry at `index` has a lower base than the entry preceding it.
+    Unsorted {
+        /// The index of the offending entry.
+        index: usize,
+    },
+    /// The entry at `index` overlaps with the entry preceding it.
+    Overlapping {
+        /// The index of the offending entry.
+        index: usize,
+    },
+    /// The output buffer has no room for the entry at `index`.
+    BufferTooSmall {
+        /// The index of the offending entry.
+        index: usize,
+    },
+    /// The entry at `index` has a size of 0.
+    ZeroSize {
+        /// The index of the offending entry.
+        index: usize,
+    },
+}
+
+impl core::fmt::Display for MemoryMapError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Unaligned { index } => write!(f, "memory map entry {index} is not aligned"),
+            Self::Unsorted { index } => write!(f, "memory map entry {index} is out of order"),
+            Self::Overlapping { index } => {
+                write!(f, "memory map entry {index} overlaps the previous entry")
+            }
+            Self::BufferTooSmall { index } => {
+                write!(
+                    f,
+                    "no room in the output buffer for memory map entry {index}"
+                )
+            }
+            Self::ZeroSize { index } => write!(f, "memory map entry {index} is empty"),
+        }
+    }
+}
+
+/// Validates that `entries` upholds all of the memory map invariants documented on
+/// [`BootloaderResponse::memory_map_entries`].
+///
+/// # Errors
+/// Returns the first [`MemoryMapError`] encountered, checking alignment, then size, then
+/// ordering, then overlap.
+///
+/// [`BootloaderResponse::memory_map_entries`]: crate::BootloaderResponse::memory_map_entries
+pub fn validate_memory_map(entries: &[MemoryMapEntry]) -> Result<(), MemoryMapError> {
+    validate_alignment(entries)?;
+    validate_non_empty(entries)?;
+    validate_sorted(entries)?;
+    validate_non_overlapping(entries)
+}
+
+/// Validates that every entry in `entries` has a 4096 byte aligned base and size.
+///
+/// # Errors
+/// Returns [`MemoryMapError::Unaligned`] for the first unaligned entry.
+pub fn validate_alignment(entries: &[MemoryMapEntry]) -> Result<(), MemoryMapError> {
+    match entries.iter().position(|entry| !is_aligned(entry)) {
+        Some(index) => Err(MemoryMapError::Unaligned { index }),
+        None => Ok(()),
+    }
+}
+
+/// Validates that no entry in `entries` has a size of 0.
+///
+/// Zero-size entries describe no memory, and so are never produced by a correct bootloader.
+///
+/// # Errors
+/// Returns [`MemoryMapError::ZeroSize`] for the first zero-size entry.
+pub fn validate_non_empty(entries: &[MemoryMapEntry]) -> Result<(), MemoryMapError> {
+    match entries.iter().position(|entry| entry.size == 0) {
+        Some(index) => Err(MemoryMapError::ZeroSize { index }),
+        None => Ok(()),
+    }
+}
+
+/// Returns `true` if any entry in `entries` has a size of 0.
+pub fn has_zero_size_entries(entries: &[MemoryMapEntry]) -> bool {
+    validate_non_empty(entries).is_err()
+}
+
+/// Returns `true` if `entry` has a 4096 byte aligned base and size.
+fn is_aligned(entry: &MemoryMapEntry) -> bool {
+    entry.base.is_multiple_of(FRAME_SIZE) && entry.size.is_multiple_of(FRAME_SIZE)
+}
+
+/// Validates that `entries` is sorted by base address, lowest to highest.
+///
+/// # Errors
+/// Returns [`MemoryMapError::Unsorted`] for the first entry with a lower base than its
+/// predecessor.
+pub fn validate_sorted(entries: &[MemoryMapEntry]) -> Result<(), MemoryMapError> {
+    match entries
+        .windows(2)
+        .position(|pair| pair[1].base < pair[0].base)
+    {
+        Some(index) => Err(MemoryMapError::Unsorted { index: index + 1 }),
+        None => Ok(()),
+    }
+}
+
+/// Validates that no entry in `entries` overlaps with the entry preceding it.
+///
+/// This only compares adjacent entries, and so is only meaningful for a sorted map.
+///
+/// # Errors
+/// Returns [`MemoryMapError::Overlapping`] for the first entry that overlaps its predecessor.
+pub fn validate_non_overlapping(entries: &[MemoryMapEntry]) -> Result<(), MemoryMapError> {
+    match entries
+        .windows(2)
+        .position(|pair| pair[0].base.saturating_add(pair[0].size) > pair[1].base)
+    {
+        Some(index) => Err(MemoryMapError::Overlapping { index: index + 1 }),
+        None => Ok(()),
+    }
+}
+
+/// Returns `true` if no entry in `entries` starts exactly where its predecessor ends while
+/// having the same kind.
+///
+/// Such pairs could be merged into a single entry. This only compares adjacent entries, and so
+/// is only meaningful for a sorted map.
+pub fn is_coalesced(entries: &[MemoryMapEntry]) -> bool {
+    !entries.windows(2).any(|pair| {
+        pair[0].kind == pair[1].kind && pair[0].base.saturating_add(pair[0].size) == pair[1].base
+    })
+}
+
+/// A range of addresses that two memory maps describe differently.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MapDiff {
+    /// The base of the differing range.
+    pub base: u64,
+    /// The size, in bytes, of the differing range.
+    pub size: u64,
+    /// The kind of the range in the first memory map, or [`None`] if it is not present.
+    pub a: Option<MemoryMapEntryKind>,
+    /// The kind of the range in the second memory map, or [`None`] if it is not present.
+    pub b: Option<MemoryMapEntryKind>,
+}
+
+/// Compares the memory maps `a` and `b`, returning every range that is present in only one of
+/// them or has a differing kind between them.
+///
+/// Adjacent differing ranges with the same pair of kinds are reported as a single [`MapDiff`].
+/// The returned [`MapDiff`]s are sorted by base address.
+#[cfg(feature = "alloc")]
+pub fn diff_memory_maps(a: &[MemoryMapEntry], b: &[MemoryMapEntry]) -> Vec<MapDiff> {
+    let boundaries = boundaries(a.iter().chain(b));
+    let mut diffs = Vec::<MapDiff>::new();
+    for window in boundaries.windows(2) {
+        let (base, end) = (window[0], window[1]);
+        let a_kind = region_containing(a, base).map(|entry| entry.kind);
+        let b_kind = region_containing(b, base).map(|entry| entry.kind);
+        if a_kind == b_kind {
+            continue;
+        }
+
+        match diffs.last_mut() {
+            Some(last) if last.base + last.size == base && last.a == a_kind && last.b == b_kind => {
+                last.size += end - base;
+            }
+            _ => diffs.push(MapDiff {
+                base,
+                size: end - base,
+                a: a_kind,
+                b: b_kind,
+            }),
+        }
+    }
+
+    diffs
+}
+
+/// Returns the base and size of the first [`MemoryMapEntryKind::USABLE`] region at or above
+/// 1 MiB, clipping a region that straddles the 1 MiB boundary.
+///
+/// Memory below 1 MiB is reserved by convention on x86 for the BIOS and legacy devices.
+#[cfg(target_arch = "x86_64")]
+pub fn first_usable_conventional(entries: &[MemoryMapEntry]) -> Option<(u64, u64)> {
+    const CONVENTIONAL_BASE: u64 = 0x100000;
+
+    entries
+        .iter()
+        .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+        .find_map(|entry| {
+            let end = entry.base.saturating_add(entry.size);
+            let base = entry.base.max(CONVENTIONAL_BASE);
+            (base < end).then(|| (base, end - base))
+        })
+}
+
+/// Returns the base of the first 4096 byte aligned [`MemoryMapEntryKind::USABLE`] page that
+/// lies entirely below 1 MiB.
+///
+/// Application processors start in real mode, and so the trampoline they start executing must
+/// be placed in such a page.
+#[cfg(target_arch = "x86_64")]
+pub fn find_trampoline_page(entries: &[MemoryMapEntry]) -> Option<u64> {
+    const TRAMPOLINE_LIMIT: u64 = 0x100000;
+
+    entries
+        .iter()
+        .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+        .find_map(|entry| {
+            let end = entry.base.saturating_add(entry.size).min(TRAMPOLINE_LIMIT);
+            let base = align_up(entry.base, FRAME_SIZE);
+            (base.checked_add(FRAME_SIZE)? <= end).then_some(base)
+        })
+}
+
+/// Accepts every [`MemoryMapEntryKind::UNACCEPTED`] entry in `entries`, changing its kind to
+/// [`MemoryMapEntryKind::USABLE`].
+///
+/// `accept` is called with the base and size of each unaccepted entry before its kind is
+/// changed, and must perform the architecture specific acceptance of the memory.
+pub fn accept_all<F: FnMut(u64, u64)>(entries: &mut [MemoryMapEntry], mut accept: F) {
+    for entry in entries
+        .iter_mut()
+        .filter(|entry| entry.kind == MemoryMapEntryKind::UNACCEPTED)
+    {
+        accept(entry.base, entry.size);
+        entry.kind = MemoryMapEntryKind::USABLE;
+    }
+}
+
+/// A set of [`MemoryMapEntryKind`]s.
+///
+/// Only the kinds with values below 64 can be members of a [`MemoryKindSet`]; this covers
+/// every kind defined by this crate.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MemoryKindSet(u64);
+
+impl MemoryKindSet {
+    /// The [`MemoryKindSet`] that contains no kinds.
+    pub const EMPTY: Self = Self(0);
+
+    /// Returns this [`MemoryKindSet`] with `kind` added.
+    ///
+    /// `kind` is not added if its value is 64 or higher.
+    pub const fn with(self, kind: MemoryMapEntryKind) -> Self {
+        if kind.0 < 64 {
+            Self(self.0 | 1 << kind.0)
+        } else {
+            self
+        }
+    }
+
+    /// Returns `true` if this [`MemoryKindSet`] contains `kind`.
+    pub const fn contains(self, kind: MemoryMapEntryKind) -> bool {
+        kind.0 < 64 && self.0 & (1 << kind.0) != 0
+    }
+}
+
+/// Returns the total size, in bytes, of the entries in `entries` whose kind is in `kinds`.
+pub fn total_bytes(entries: &[MemoryMapEntry], kinds: MemoryKindSet) -> u64 {
+    entries
+        .iter()
+        .filter(|entry| kinds.contains(entry.kind))
+        .fold(0, |total, entry| total.saturating_add(entry.size))
+}
+
+/// Returns the total size, in bytes, of the entries in `entries` that will never become
+/// usable.
+///
+/// This counts [`MemoryMapEntryKind::RESERVED`], [`MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE`],
+/// [`MemoryMapEntryKind::UNUSABLE`], [`MemoryMapEntryKind::KERNEL`], and
+/// [`MemoryMapEntryKind::MODULE`] entries, but not reclaimable ones such as
+/// [`MemoryMapEntryKind::ACPI_RECLAIMABLE`] or [`MemoryMapEntryKind::BOOTLOADER`].
+pub fn permanently_reserved_bytes(entries: &[MemoryMapEntry]) -> u64 {
+    const PERMANENTLY_RESERVED: MemoryKindSet = MemoryKindSet::EMPTY
+        .with(MemoryMapEntryKind::RESERVED)
+        .with(MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE)
+        .with(MemoryMapEntryKind::UNUSABLE)
+        .with(MemoryMapEntryKind::KERNEL)
+        .with(MemoryMapEntryKind::MODULE);
+
+    total_bytes(entries, PERMANENTLY_RESERVED)
+}
+
+/// Returns the largest contiguous range formed by merging adjacent entries in `entries` whose
+/// kind is in `kinds`.
+///
+/// `entries` must be sorted by base address.
+pub fn largest_contiguous_usable(
+    entries: &[MemoryMapEntry],
+    kinds: MemoryKindSet,
+) -> Option<core::ops::Range<u64>> {
+    let mut largest: Option<core::ops::Range<u64>> = None;
+    let mut current: Option<core::ops::Range<u64>> = None;
+
+    for entry in entries.iter().filter(|entry| kinds.contains(entry.kind)) {
+        let end = entry.base.saturating_add(entry.size);
+        let range = match current {
+            Some(range) if range.end == entry.base => range.start..end,
+            _ => entry.base..end,
+        };
+
+        if largest
+            .as_ref()
+            .is_none_or(|largest| range.end - range.start > largest.end - largest.start)
+        {
+            largest = Some(range.clone());
+        }
+        current = Some(range);
+    }
+
+    largest
+}
+
+/// Returns an iterator over the portions of the [`MemoryMapEntryKind::USABLE`] entries in
+/// `entries` that lie below `limit`, clipping entries that straddle `limit`.
+pub fn usable_below(
+    entries: &[MemoryMapEntry],
+    limit: u64,
+) -> impl Iterator<Item = core::ops::Range<u64>> + '_ {
+    entries
+        .iter()
+        .filter(move |entry| entry.kind == MemoryMapEntryKind::USABLE && entry.base < limit)
+        .map(move |entry| entry.base..entry.base.saturating_add(entry.size).min(limit))
+        .filter(|range| !range.is_empty())
+}
+
+/// Returns the total size, in bytes, of the portions of the [`MemoryMapEntryKind::USABLE`]
+/// entries in `entries` that lie at or above `threshold`, clipping entries that straddle
+/// `threshold`.
+pub fn usable_bytes_above(entries: &[MemoryMapEntry], threshold: u64) -> u64 {
+    entries
+        .iter()
+        .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+        .map(|entry| {
+            entry
+                .base
+                .saturating_add(entry.size)
+                .saturating_sub(entry.base.max(threshold))
+        })
+        .fold(0, u64::saturating_add)
+}
+
+/// Returns `true` if every byte of `range` lies within [`MemoryMapEntryKind::USABLE`] entries
+/// in `entries`.
+///
+/// `range` may span several adjacent usable entries. This performs a linear search per entry
+/// crossed and so does not require `entries` to be sorted. An empty `range` is always usable.
+pub fn is_range_usable(entries: &[MemoryMapEntry], range: core::ops::Range<u64>) -> bool {
+    let mut start = range.start;
+    while start < range.end {
+        let Some(entry) = entries
+            .iter()
+            .find(|entry| entry.kind == MemoryMapEntryKind::USABLE && contains(entry, start))
+        else {
+            return false;
+        };
+        start = entry.base.saturating_add(entry.size);
+    }
+
+    true
+}
+
+/// Returns the base and size of the first [`MemoryMapEntryKind::USABLE`] entry in `entries`
+/// that is at least `min_size` bytes long.
+///
+/// Unlike [`largest_contiguous_usable`], this stops at the first sufficient entry, which is
+/// all an early bump allocator needs.
+pub fn bump_region(entries: &[MemoryMapEntry], min_size: u64) -> Option<(u64, u64)> {
+    entries
+        .iter()
+        .find(|entry| entry.kind == MemoryMapEntryKind::USABLE && entry.size >= min_size)
+        .map(|entry| (entry.base, entry.size))
+}
+
+/// Proof that everything the kernel needs from [`MemoryMapEntryKind::BOOTLOADER`] memory has
+/// been copied out, allowing that memory to be reclaimed.
+#[derive(Debug)]
+pub struct ReclaimGuard {
+    _private: (),
+}
+
+impl ReclaimGuard {
+    /// Creates a new [`ReclaimGuard`].
+    ///
+    /// # Safety
+    /// Nothing stored in [`MemoryMapEntryKind::BOOTLOADER`] memory, including the
+    /// [`BootloaderResponse`] and every structure it points to, may be accessed after this
+    /// [`ReclaimGuard`] is created.
+    ///
+    /// [`BootloaderResponse`]: crate::BootloaderResponse
+    pub const unsafe fn new() -> Self {
+        Self { _private: () }
+    }
+
+    /// Changes the kind of every [`MemoryMapEntryKind::BOOTLOADER`] entry in `entries` to
+    /// [`MemoryMapEntryKind::USABLE`], returning the number of entries reclaimed.
+    pub fn reclaim(&self, entries: &mut [MemoryMapEntry]) -> usize {
+        let mut reclaimed = 0;
+        for entry in entries
+            .iter_mut()
+            .filter(|entry| entry.kind == MemoryMapEntryKind::BOOTLOADER)
+        {
+            entry.kind = MemoryMapEntryKind::USABLE;
+            reclaimed += 1;
+        }
+        reclaimed
+    }
+}
+
+/// Returns an iterator over the [`MemoryMapEntryKind::USABLE`] entries in `entries` that stops
+/// at the first malformed entry.
+///
+/// An entry is malformed if it is not 4096 byte aligned or if it starts before the end of the
+/// entry preceding it. Unlike skipping malformed entries, this never yields an entry that
+/// follows a malformed one.
+pub fn usable_until_malformed(entries: &[MemoryMapEntry]) -> impl Iterator<Item = &MemoryMapEntry> {
+    entries
+        .iter()
+        .scan(None::<&MemoryMapEntry>, |previous, entry| {
+            let ordered = previous
+                .is_none_or(|previous| previous.base.saturating_add(previous.size) <= entry.base);
+            if !is_aligned(entry) || !ordered {
+                return None;
+            }
+
+            *previous = Some(entry);
+            Some(entry)
+        })
+        .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+}
+
+/// The number of [`MemoryMapEntryKind`]s defined by this crate.
+const KNOWN_KINDS: usize = MemoryMapEntryKind::MODULE.0 as usize + 1;
+
+/// The number of 4096 byte frames occupied by each [`MemoryMapEntryKind`] in a memory map.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KindFrameCounts {
+    known: [u64; KNOWN_KINDS],
+    unknown: u64,
+}
+
+impl KindFrameCounts {
+    /// Returns the number of frames occupied by entries of `kind`.
+    ///
+    /// All kinds not defined by this crate share a single count.
+    pub const fn get(&self, kind: MemoryMapEntryKind) -> u64 {
+        if kind.0 < KNOWN_KINDS as u64 {
+            self.known[kind.0 as usize]
+        } else {
+            self.unknown
+        }
+    }
+
+    /// Returns the number of frames occupied by entries of kinds not defined by this crate.
+    pub const fn unknown(&self) -> u64 {
+        self.unknown
+    }
+
+    /// Returns the total number of frames counted.
+    pub fn total_frames(&self) -> u64 {
+        self.known
+            .iter()
+            .fold(self.unknown, |total, &count| total.saturating_add(count))
+    }
+}
+
+/// Returns the number of 4096 byte frames occupied by each [`MemoryMapEntryKind`] in
+/// `entries`.
+pub fn frame_counts(entries: &[MemoryMapEntry]) -> KindFrameCounts {
+    let mut counts = KindFrameCounts::default();
+    for entry in entries {
+        let index = usize::try_from(entry.kind.0).unwrap_or(usize::MAX);
+        let count = match counts.known.get_mut(index) {
+            Some(count) => count,
+            None => &mut counts.unknown,
+        };
+        *count = count.saturating_add(entry.size / FRAME_SIZE);
+    }
+    counts
+}
+
+/// Returns the number of distinct [`MemoryMapEntryKind`]s that appear in `entries`.
+///
+/// All kinds not defined by this crate are counted as a single kind.
+pub fn distinct_kinds(entries: &[MemoryMapEntry]) -> u32 {
+    let mut known = MemoryKindSet::EMPTY;
+    let mut unknown = false;
+    for entry in entries {
+        if entry.kind.0 < KNOWN_KINDS as u64 {
+            known = known.with(entry.kind);
+        } else {
+            unknown = true;
+        }
+    }
+    known.0.count_ones() + u32::from(unknown)
+}
+
+/// Returns the precedence of `kind` when resolving overlaps in [`merge_memory_maps`].
+///
+/// Higher values are more restrictive. Kinds not defined by this crate are treated as
+/// [`MemoryMapEntryKind::RESERVED`].
+#[cfg(feature = "alloc")]
+fn kind_precedence(kind: MemoryMapEntryKind) -> u8 {
+    match kind {
+        MemoryMapEntryKind::USABLE => 0,
+        MemoryMapEntryKind::UNACCEPTED => 1,
+        MemoryMapEntryKind::BOOTLOADER => 2,
+        MemoryMapEntryKind::ACPI_RECLAIMABLE => 3,
+        MemoryMapEntryKind::MODULE => 4,
+        MemoryMapEntryKind::KERNEL => 5,
+        MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE => 6,
+        MemoryMapEntryKind::UNUSABLE => 8,
+        _ => 7,
+    }
+}
+
+/// Combines the memory maps `a` and `b` into a single sorted, non-overlapping memory map.
+///
+/// Where entries overlap, the more restrictive kind is kept, using the following precedence
+/// from least to most restrictive:
+///
+/// 1. [`MemoryMapEntryKind::USABLE`]
+/// 2. [`MemoryMapEntryKind::UNACCEPTED`]
+/// 3. [`MemoryMapEntryKind::BOOTLOADER`]
+/// 4. [`MemoryMapEntryKind::ACPI_RECLAIMABLE`]
+/// 5. [`MemoryMapEntryKind::MODULE`]
+/// 6. [`MemoryMapEntryKind::KERNEL`]
+/// 7. [`MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE`]
+/// 8. [`MemoryMapEntryKind::RESERVED`] and any kind not defined by this crate
+/// 9. [`MemoryMapEntryKind::UNUSABLE`]
+///
+/// Adjacent ranges of the same kind are merged into a single entry. If every entry of `a` and
+/// `b` is 4096 byte aligned, the result passes [`validate_memory_map`].
+#[cfg(feature = "alloc")]
+pub fn merge_memory_maps(a: &[MemoryMapEntry], b: &[MemoryMapEntry]) -> Vec<MemoryMapEntry> {
+    sweep(a.iter().chain(b), |address| {
+        a.iter()
+            .chain(b)
+            .filter(|entry| contains(entry, address))
+            .map(|entry| entry.kind)
+            .max_by_key(|&kind| kind_precedence(kind))
+    })
+}
+
+/// Returns `true` if `entry` contains `address`.
+fn contains(entry: &MemoryMapEntry, address: u64) -> bool {
+    address >= entry.base && address - entry.base < entry.size
+}
+
+/// Returns the sorted, deduplicated set of addresses at which an entry of `entries` starts or
+/// ends.
+#[cfg(feature = "alloc")]
+fn boundaries<'a>(entries: impl Iterator<Item = &'a MemoryMapEntry>) -> Vec<u64> {
+    let mut boundaries = entries
+        .flat_map(|entry| [entry.base, entry.base.saturating_add(entry.size)])
+        .collect::<Vec<u64>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries
+}
+
+/// Builds a sorted, non-overlapping memory map covering the boundaries of `entries`, using
+/// `kind_at` to determine the kind of each range between consecutive boundaries.
+///
+/// Ranges for which `kind_at` returns [`None`] are left out, and adjacent ranges of the same
+/// kind are merged into a single entry.
+#[cfg(feature = "alloc")]
+fn sweep<'a>(
+    entries: impl Iterator<Item = &'a MemoryMapEntry>,
+    mut kind_at: impl FnMut(u64) -> Option<MemoryMapEntryKind>,
+) -> Vec<MemoryMapEntry> {
+    let mut map = Vec::<MemoryMapEntry>::new();
+    for window in boundaries(entries).windows(2) {
+        let (base, end) = (window[0], window[1]);
+        let Some(kind) = kind_at(base) else {
+            continue;
+        };
+
+        match map.last_mut() {
+            Some(last) if last.base + last.size == base && last.kind == kind => {
+                last.size += end - base;
+            }
+            _ => map.push(MemoryMapEntry {
+                kind,
+                base,
+                size: end - base,
+            }),
+        }
+    }
+
+    map
+}
+
+/// A builder of a final memory map from the raw regions reported by firmware and the
+/// placements made by the bootloader.
+///
+/// Each reservation is punched into the raw regions, splitting them as needed, so that the
+/// bootloader does not need to carve [`MemoryMapEntryKind::KERNEL`] and
+/// [`MemoryMapEntryKind::MODULE`] entries out of usable memory by hand.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct MapBuilder {
+    regions: Vec<MemoryMapEntry>,
+    reservations: Vec<MemoryMapEntry>,
+}
+
+#[cfg(feature = "alloc")]
+impl MapBuilder {
+    /// Creates a new, empty [`MapBuilder`].
+    pub const fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+            reservations: Vec::new(),
+        }
+    }
+
+    /// Adds a raw region reported by firmware.
+    ///
+    /// Where raw regions overlap, the more restrictive kind is kept, as in
+    /// [`merge_memory_maps`].
+    pub fn region(&mut self, base: u64, size: u64, kind: MemoryMapEntryKind) -> &mut Self {
+        self.regions.push(MemoryMapEntry { kind, base, size });
+        self
+    }
+
+    /// Reserves `size` bytes starting at `base` as `kind`, overriding the kind of any raw
+    /// region it overlaps.
+    ///
+    /// Where reservations overlap, the reservation added last is kept.
+    pub fn reserve(&mut self, base: u64, size: u64, kind: MemoryMapEntryKind) -> &mut Self {
+        self.reservations.push(MemoryMapEntry { kind, base, size });
+        self
+    }
+
+    /// Produces the final, sorted, non-overlapping memory map.
+    ///
+    /// # Errors
+    /// Returns the [`MemoryMapError`] reported by [`validate_memory_map`] if the resulting
+    /// memory map is invalid.
+    pub fn build(&self) -> Result<Vec<MemoryMapEntry>, MemoryMapError> {
+        let map = sweep(self.regions.iter().chain(&self.reservations), |address| {
+            self.reservations
+                .iter()
+                .rev()
+                .find(|entry| contains(entry, address))
+                .or_else(|| {
+                    self.regions
+                        .iter()
+                        .filter(|entry| contains(entry, address))
+                        .max_by_key(|entry| kind_precedence(entry.kind))
+                })
+                .map(|entry| entry.kind)
+        });
+
+        validate_memory_map(&map)?;
+        Ok(map)
+    }
+}
+
+/// Writes the portions of `entries` that lie within `window` into `out`, truncating entries
+/// that lie partially within `window`, and returns the number of entries written.
+///
+/// # Errors
+/// Returns [`MemoryMapError::BufferTooSmall`] if `out` cannot hold every clipped entry.
+pub fn clip_to_window(
+    entries: &[MemoryMapEntry],
+    window: core::ops::Range<u64>,
+    out: &mut [MemoryMapEntry],
+) -> Result<usize, MemoryMapError> {
+    let mut count = 0;
+    for (index, entry) in enumerate_memory_map(entries) {
+        let base = entry.base.max(window.start);
+        let end = entry.base.saturating_add(entry.size).min(window.end);
+        if base >= end {
+            continue;
+        }
+
+        let slot = out
+            .get_mut(count)
+            .ok_or(MemoryMapError::BufferTooSmall { index })?;
+        *slot = MemoryMapEntry {
+            kind: entry.kind,
+            base,
+            size: end - base,
+        };
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// A region of usable memory to be managed by its own allocator.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Arena {
+    /// The base of the region.
+    pub base: u64,
+    /// The size, in bytes, of the region.
+    pub size: u64,
+}
+
+/// Returns an iterator yielding one [`Arena`] per [`MemoryMapEntryKind::USABLE`] entry in
+/// `entries`.
+///
+/// Arenas are never merged across entries, so adjacent usable entries that should share an
+/// allocator must be merged before calling this.
+pub fn usable_regions_as_arenas(entries: &[MemoryMapEntry]) -> impl Iterator<Item = Arena> + '_ {
+    entries
+        .iter()
+        .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+        .map(|entry| Arena {
+            base: entry.base,
+            size: entry.size,
+        })
+}
+
+/// A precomputed index over a memory map for fast repeated lookups.
+///
+/// For small memory maps, [`region_containing`] is just as fast.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct MemoryMapIndex {
+    entries: Vec<MemoryMapEntry>,
+    usable: Vec<MemoryMapEntry>,
+}
+
+#[cfg(feature = "alloc")]
+impl MemoryMapIndex {
+    /// Creates a new [`MemoryMapIndex`] over a copy of `entries`.
+    ///
+    /// `entries` need not be sorted, but must not overlap.
+    pub fn new(entries: &[MemoryMapEntry]) -> Self {
+        let mut entries = entries.to_vec();
+        entries.sort_unstable_by_key(|entry| entry.base);
+        let usable = entries
+            .iter()
+            .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+            .copied()
+            .collect();
+
+        Self { entries, usable }
+    }
+
+    /// Returns the entry that contains `address`, if any, in `O(log n)` time.
+    pub fn region_containing(&self, address: u64) -> Option<&MemoryMapEntry> {
+        Self::search(&self.entries, address).filter(|entry| contains(entry, address))
+    }
+
+    /// Returns the kind of the entry that contains `address`, if any, in `O(log n)` time.
+    pub fn kind_at(&self, address: u64) -> Option<MemoryMapEntryKind> {
+        self.region_containing(address).map(|entry| entry.kind)
+    }
+
+    /// Returns the [`MemoryMapEntryKind::USABLE`] entry closest to `address`, preferring the
+    /// entry that contains `address` and, on a tie, the lower entry.
+    pub fn nearest_usable(&self, address: u64) -> Option<&MemoryMapEntry> {
+        let distance = |entry: &MemoryMapEntry| {
+            if address < entry.base {
+                entry.base - address
+            } else {
+                (address - entry.base).saturating_sub(entry.size.saturating_sub(1))
+            }
+        };
+
+        let index = self.usable.partition_point(|entry| entry.base <= address);
+        let below = index
+            .checked_sub(1)
+            .and_then(|index| self.usable.get(index));
+        let above = self.usable.get(index);
+        match (below, above) {
+            (Some(below), Some(above)) if distance(above) < distance(below) => Some(above),
+            (Some(below), _) => Some(below),
+            (None, above) => above,
+        }
+    }
+
+    /// Returns the entry in `entries` with the highest base at or below `address`.
+    fn search(entries: &[MemoryMapEntry], address: u64) -> Option<&MemoryMapEntry> {
+        let index = entries.partition_point(|entry| entry.base <= address);
+        entries.get(index.checked_sub(1)?)
+    }
+}
+
+/// Various errors that can occur when parsing a serialized memory map.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ParseError {
+    /// The length of the blob is not a multiple of the size of a [`MemoryMapEntry`].
+    BadLength,
+    /// The blob is not properly aligned for a [`MemoryMapEntry`].
+    Misaligned,
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::BadLength => f.write_str("memory map blob has a truncated entry"),
+            Self::Misaligned => f.write_str("memory map blob is misaligned"),
+        }
+    }
+}
+
+/// Reinterprets `blob` as a slice of [`MemoryMapEntry`]s without copying.
+///
+/// The entries themselves are not validated; use [`validate_memory_map`] for that. An empty
+/// `blob` always parses to an empty slice, regardless of its alignment.
+///
+/// # Errors
+/// - [`ParseError::BadLength`]: the length of `blob` is not a multiple of the size of a
+///   [`MemoryMapEntry`].
+/// - [`ParseError::Misaligned`]: `blob` is not aligned to a [`MemoryMapEntry`].
+pub fn parse_memory_map(blob: &[u8]) -> Result<&[MemoryMapEntry], ParseError> {
+    if blob.is_empty() {
+        return Ok(&[]);
+    }
+    if !blob
+        .len()
+        .is_multiple_of(core::mem::size_of::<MemoryMapEntry>())
+    {
+        return Err(ParseError::BadLength);
+    }
+    if blob
+        .as_ptr()
+        .align_offset(core::mem::align_of::<MemoryMapEntry>())
+        != 0
+    {
+        return Err(ParseError::Misaligned);
+    }
+
+    // SAFETY: `blob` is aligned for `MemoryMapEntry` and holds a whole number of entries, and
+    // every bit pattern is a valid `MemoryMapEntry`.
+    Ok(unsafe {
+        core::slice::from_raw_parts(
+            blob.as_ptr().cast::<MemoryMapEntry>(),
+            blob.len() / core::mem::size_of::<MemoryMapEntry>(),
+        )
+    })
+}
+
+/// Returns an iterator over the [`MemoryMapEntryKind::UNACCEPTED`] memory in `entries`,
+/// coalescing adjacent unaccepted entries and splitting the result into batches of at most
+/// `max_batch` bytes.
+///
+/// If `max_batch` is 0, coalesced ranges are never split. `entries` must be sorted by base
+/// address.
+pub fn unaccepted_batches(
+    entries: &[MemoryMapEntry],
+    max_batch: u64,
+) -> impl Iterator<Item = core::ops::Range<u64>> + '_ {
+    let mut unaccepted = entries
+        .iter()
+        .filter(|entry| entry.kind == MemoryMapEntryKind::UNACCEPTED)
+        .peekable();
+    let runs = core::iter::from_fn(move || {
+        let first = unaccepted.next()?;
+        let mut run = first.base..first.end_addr().as_u64();
+        while let Some(entry) = unaccepted.next_if(|entry| entry.base == run.end) {
+            run.end = entry.end_addr().as_u64();
+        }
+        Some(run)
+    });
+
+    runs.flat_map(move |mut run| {
+        core::iter::from_fn(move || {
+            if run.is_empty() {
+                return None;
+            }
+
+            let end = match max_batch {
+                0 => run.end,
+                max_batch => run.start.saturating_add(max_batch).min(run.end),
+            };
+            let batch = run.start..end;
+            run.start = end;
+            Some(batch)
+        })
+    })
+}
+
+/// Returns an iterator over the `page_size` aligned ranges that cover every entry in
+/// `entries` that is not [`MemoryMapEntryKind::USABLE`], merging ranges that touch once
+/// aligned.
+///
+/// Each range can be mapped entirely using pages of `page_size` bytes. `page_size` must be a
+/// power of two and `entries` must be sorted by base address. A range that would extend past
+/// the end of the address space ends at [`u64::MAX`] instead.
+pub fn huge_page_ranges(
+    entries: &[MemoryMapEntry],
+    page_size: u64,
+) -> impl Iterator<Item = core::ops::Range<u64>> + '_ {
+    let mut aligned = entries
+        .iter()
+        .filter(|entry| entry.kind != MemoryMapEntryKind::USABLE && entry.size != 0)
+        .map(move |entry| {
+            align_down(entry.base, page_size)..align_up(entry.end_addr().as_u64(), page_size)
+        })
+        .peekable();
+
+    core::iter::from_fn(move || {
+        let mut range = aligned.next()?;
+        while let Some(next) = aligned.next_if(|next| next.start <= range.end) {
+            range.end = range.end.max(next.end);
+        }
+        Some(range)
+    })
+}
+
+/// Encodes the [`MemoryMapEntryKind::USABLE`] entries in `entries` as the `reg` property of
+/// a devicetree `/memory` node.
+///
+/// Each entry is encoded as its base in `address_cells` big-endian 32-bit cells followed by its
+/// size in `size_cells` big-endian 32-bit cells. Bits that do not fit in the given number of
+/// cells are discarded.
+#[cfg(feature = "alloc")]
+pub fn memory_map_to_dt_reg(
+    entries: &[MemoryMapEntry],
+    address_cells: u32,
+    size_cells: u32,
+) -> Vec<u8> {
+    fn push_cells(reg: &mut Vec<u8>, value: u64, cells: u32) {
+        for cell in (0..cells).rev() {
+            let cell = value.checked_shr(32 * cell).unwrap_or(0) as u32;
+            reg.extend_from_slice(&cell.to_be_bytes());
+        }
+    }
+
+    let mut reg = Vec::new();
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+    {
+        push_cells(&mut reg, entry.base, address_cells);
+        push_cells(&mut reg, entry.size, size_cells);
+    }
+    reg
+}
+
+/// Folds the kind, base, and size of `entry` into the 64-bit FNV-1a `hash`.
+const fn hash_entry(hash: u64, entry: &MemoryMapEntry) -> u64 {
+    let hash = crate::fnv1a_bytes(hash, &entry.kind.0.to_le_bytes());
+    let hash = crate::fnv1a_bytes(hash, &entry.base.to_le_bytes());
+    crate::fnv1a_bytes(hash, &entry.size.to_le_bytes())
+}
+
+/// Returns a hash of the kind, base, and size of every entry in `entries`, in order.
+///
+/// This is the value the bootloader stores in [`BootloaderResponse::memory_map_checksum`].
+///
+/// [`BootloaderResponse::memory_map_checksum`]: crate::BootloaderResponse::memory_map_checksum
+pub const fn checksum(entries: &[MemoryMapEntry]) -> u64 {
+    let mut hash = crate::FNV_OFFSET_BASIS;
+    let mut index = 0;
+    while index < entries.len() {
+        hash = hash_entry(hash, &entries[index]);
+        index += 1;
+    }
+    hash
+}
+
+/// Returns a hash of the kind, base, and size of every entry in `entries` that is not
+/// [`MemoryMapEntryKind::USABLE`].
+///
+/// Usable entries are skipped because their exact bounds may shift between boots, whereas
+/// firmware-owned regions should not. Comparing the signatures of memory maps from different
+/// boots therefore detects changes to the firmware's layout.
+#[cfg(feature = "alloc")]
+pub fn layout_signature(entries: &[MemoryMapEntry]) -> u64 {
+    entries
+        .iter()
+        .filter(|entry| entry.kind != MemoryMapEntryKind::USABLE)
+        .fold(crate::FNV_OFFSET_BASIS, hash_entry)
+}
+
+/// A memory map, offering the free functions of this module as methods.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct MemoryMap<'a>(&'a [MemoryMapEntry]);
+
+impl<'a> MemoryMap<'a> {
+    /// Creates a new [`MemoryMap`] over `entries`.
+    pub const fn new(entries: &'a [MemoryMapEntry]) -> Self {
+        Self(entries)
+    }
+
+    /// Returns the entries of this [`MemoryMap`].
+    pub const fn as_slice(self) -> &'a [MemoryMapEntry] {
+        self.0
+    }
+
+    /// Returns an iterator over the [`MemoryMapEntryKind::USABLE`] entries of this
+    /// [`MemoryMap`].
+    pub fn usable(self) -> impl Iterator<Item = &'a MemoryMapEntry> {
+        self.0
+            .iter()
+            .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+    }
+
+    /// Validates this [`MemoryMap`] using [`validate_memory_map`].
+    ///
+    /// # Errors
+    /// Returns the [`MemoryMapError`] describing the first invalid entry.
+    pub fn validate(self) -> Result<(), MemoryMapError> {
+        validate_memory_map(self.0)
+    }
+
+    /// Returns the total size, in bytes, of the [`MemoryMapEntryKind::USABLE`] entries of this
+    /// [`MemoryMap`].
+    pub fn total_usable_bytes(self) -> u64 {
+        total_bytes(
+            self.0,
+            MemoryKindSet::EMPTY.with(MemoryMapEntryKind::USABLE),
+        )
+    }
+
+    /// Returns the entry that contains `address`, if any, using [`region_containing`].
+    pub fn region_containing(self, address: u64) -> Option<&'a MemoryMapEntry> {
+        region_containing(self.0, address)
+    }
+}
+
+impl<'a> IntoIterator for MemoryMap<'a> {
+    type Item = &'a MemoryMapEntry;
+    type IntoIter = core::slice::Iter<'a, MemoryMapEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// The base of a [`MemoryMapEntry`], identifying it within a valid memory map.
+///
+/// Since [`RegionKey`] implements [`Borrow<u64>`](core::borrow::Borrow) and orders like its
+/// base, a sorted container keyed on [`RegionKey`] can be searched by raw address.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RegionKey(pub u64);
+
+impl From<&MemoryMapEntry> for RegionKey {
+    fn from(entry: &MemoryMapEntry) -> Self {
+        Self(entry.base_key())
+    }
+}
+
+impl From<u64> for RegionKey {
+    fn from(base: u64) -> Self {
+        Self(base)
+    }
+}
+
+impl From<RegionKey> for u64 {
+    fn from(key: RegionKey) -> Self {
+        key.0
+    }
+}
+
+impl core::borrow::Borrow<u64> for RegionKey {
+    fn borrow(&self) -> &u64 {
+        &self.0
+    }
+}
+
+/// Returns an iterator that validates each entry of `entries` as it is yielded.
+///
+/// See [`ValidatedIter`] for the checks performed.
+pub fn validated_iter(entries: &[MemoryMapEntry]) -> ValidatedIter<'_> {
+    ValidatedIter {
+        entries: entries.iter().enumerate(),
+        previous: None,
+        failed: false,
+    }
+}
+
+/// An iterator over a memory map that checks each entry for alignment and size, and against
+/// the previous entry for ordering and overlap, as it goes.
+///
+/// The first invalid entry is yielded as a [`MemoryMapError`], after which iteration ends.
+/// Since each entry is fully checked before moving on to the next, this may report a
+/// different error than [`validate_memory_map`], which checks every entry's alignment first.
+#[derive(Clone, Debug)]
+pub struct ValidatedIter<'a> {
+    entries: core::iter::Enumerate<core::slice::Iter<'a, MemoryMapEntry>>,
+    previous: Option<&'a MemoryMapEntry>,
+    failed: bool,
+}
+
+impl<'a> Iterator for ValidatedIter<'a> {
+    type Item = Result<&'a MemoryMapEntry, MemoryMapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let (index, entry) = self.entries.next()?;
+        let error = if !is_aligned(entry) {
+            Some(MemoryMapError::Unaligned { index })
+        } else if entry.size == 0 {
+            Some(MemoryMapError::ZeroSize { index })
+        } else {
+            match self.previous {
+                Some(previous) if entry.base < previous.base => {
+                    Some(MemoryMapError::Unsorted { index })
+                }
+                Some(previous) if previous.base.saturating_add(previous.size) > entry.base => {
+                    Some(MemoryMapError::Overlapping { index })
+                }
+                _ => None,
+            }
+        };
+
+        self.previous = Some(entry);
+        match error {
+            Some(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+            None => Some(Ok(entry)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::entry;
+
+    #[test]
+    fn bitmap_size_rounds_up_unaligned_end() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 16 * FRAME_SIZE),
+            entry(MemoryMapEntryKind::RESERVED, 16 * FRAME_SIZE, 1),
+        ];
+
+        assert_eq!(highest_address(&entries), 16 * FRAME_SIZE + 1);
+        assert_eq!(physical_frame_count(&entries), 17);
+        assert_eq!(physical_bitmap_bytes(&entries), 3);
+        assert_eq!(physical_bitmap_bytes(&entries[..1]), 2);
+        assert_eq!(physical_bitmap_bytes(&[]), 0);
+    }
+
+    #[test]
+    fn bitmap_size_saturates() {
+        let entries = [entry(
+            MemoryMapEntryKind::USABLE,
+            u64::MAX - FRAME_SIZE,
+            2 * FRAME_SIZE,
+        )];
+
+        assert_eq!(highest_address(&entries), u64::MAX);
+        assert_eq!(
+            physical_frame_count(&entries),
+            u64::MAX.div_ceil(FRAME_SIZE)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn diff_reports_single_differing_region() {
+        let a = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x4000),
+            entry(MemoryMapEntryKind::RESERVED, 0x4000, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x6000, 0x4000),
+        ];
+        let b = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x4000),
+            entry(MemoryMapEntryKind::RESERVED, 0x4000, 0x1000),
+            entry(MemoryMapEntryKind::ACPI_RECLAIMABLE, 0x5000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x6000, 0x4000),
+        ];
+
+        assert_eq!(
+            diff_memory_maps(&a, &b),
+            [MapDiff {
+                base: 0x5000,
+                size: 0x1000,
+                a: Some(MemoryMapEntryKind::RESERVED),
+                b: Some(MemoryMapEntryKind::ACPI_RECLAIMABLE),
+            }]
+        );
+        assert!(diff_memory_maps(&a, &a).is_empty());
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn first_usable_conventional_clips_at_1_mib() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x9f000),
+            entry(MemoryMapEntryKind::RESERVED, 0x9f000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0xa0000, 0x100000),
+        ];
+        assert_eq!(
+            first_usable_conventional(&entries),
+            Some((0x100000, 0xa0000))
+        );
+        assert_eq!(first_usable_conventional(&entries[..2]), None);
+    }
+
+    #[test]
+    fn error_index_matches_enumeration() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x800),
+        ];
+
+        let (position, _) = enumerate_memory_map(&entries)
+            .find(|(_, entry)| !is_aligned(entry))
+            .unwrap();
+        assert_eq!(
+            validate_memory_map(&entries),
+            Err(MemoryMapError::Unaligned { index: position })
+        );
+        assert_eq!(position, 2);
+    }
+
+    #[test]
+    fn accept_all_accepts_unaccepted_entries() {
+        let mut entries = [
+            entry(MemoryMapEntryKind::UNACCEPTED, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::UNACCEPTED, 0x2000, 0x3000),
+        ];
+
+        let mut accepted = std::vec::Vec::new();
+        accept_all(&mut entries, |base, size| accepted.push((base, size)));
+        assert_eq!(accepted, [(0, 0x1000), (0x2000, 0x3000)]);
+        assert_eq!(
+            entries.map(|entry| entry.kind),
+            [
+                MemoryMapEntryKind::USABLE,
+                MemoryMapEntryKind::RESERVED,
+                MemoryMapEntryKind::USABLE,
+            ]
+        );
+    }
+
+    #[test]
+    fn largest_contiguous_bridges_adjacent_regions() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x4000, 0x2000),
+            entry(MemoryMapEntryKind::RESERVED, 0x6000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x7000, 0x5000),
+        ];
+        let usable = MemoryKindSet::EMPTY.with(MemoryMapEntryKind::USABLE);
+
+        assert_eq!(largest_contiguous_usable(&entries, usable), Some(0..0x6000));
+        assert_eq!(
+            largest_contiguous_usable(&entries, usable.with(MemoryMapEntryKind::RESERVED)),
+            Some(0..0xc000)
+        );
+        assert_eq!(
+            largest_contiguous_usable(&entries, MemoryKindSet::EMPTY),
+            None
+        );
+    }
+
+    #[test]
+    fn usable_below_clips_at_limit() {
+        const LIMIT: u64 = 0x1_0000_0000;
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, LIMIT - 0x4000, 0x8000),
+            entry(MemoryMapEntryKind::USABLE, LIMIT + 0x8000, 0x1000),
+        ];
+
+        assert!(usable_below(&entries, LIMIT).eq([0x1000..0x2000, LIMIT - 0x4000..LIMIT]));
+        assert_eq!(usable_below(&entries, 0x1000).count(), 0);
+    }
+
+    #[test]
+    fn reclaim_only_touches_bootloader_entries() {
+        let mut entries = [
+            entry(MemoryMapEntryKind::BOOTLOADER, 0, 0x1000),
+            entry(MemoryMapEntryKind::KERNEL, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::BOOTLOADER, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::ACPI_RECLAIMABLE, 0x3000, 0x1000),
+        ];
+
+        // SAFETY: nothing is stored in the bootloader memory described by `entries`.
+        let guard = unsafe { ReclaimGuard::new() };
+        assert_eq!(guard.reclaim(&mut entries), 2);
+        assert_eq!(
+            entries.map(|entry| entry.kind),
+            [
+                MemoryMapEntryKind::USABLE,
+                MemoryMapEntryKind::KERNEL,
+                MemoryMapEntryKind::USABLE,
+                MemoryMapEntryKind::ACPI_RECLAIMABLE,
+            ]
+        );
+        assert_eq!(guard.reclaim(&mut entries), 0);
+    }
+
+    #[test]
+    fn usable_until_malformed_stops_at_malformed_entry() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x3000, 0x800),
+            entry(MemoryMapEntryKind::USABLE, 0x4000, 0x1000),
+        ];
+
+        assert!(usable_until_malformed(&entries).eq([&entries[0], &entries[2]]));
+    }
+
+    #[test]
+    fn frame_counts_sum_per_kind() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 2 * FRAME_SIZE),
+            entry(MemoryMapEntryKind::RESERVED, 2 * FRAME_SIZE, FRAME_SIZE),
+            entry(MemoryMapEntryKind::USABLE, 3 * FRAME_SIZE, 5 * FRAME_SIZE),
+            entry(MemoryMapEntryKind(42), 8 * FRAME_SIZE, FRAME_SIZE),
+            entry(MemoryMapEntryKind::RESERVED, 9 * FRAME_SIZE, 3 * FRAME_SIZE),
+        ];
+
+        let counts = frame_counts(&entries);
+        assert_eq!(counts.get(MemoryMapEntryKind::USABLE), 7);
+        assert_eq!(counts.get(MemoryMapEntryKind::RESERVED), 4);
+        assert_eq!(counts.get(MemoryMapEntryKind::KERNEL), 0);
+        assert_eq!(counts.unknown(), 1);
+        assert_eq!(counts.total_frames(), 12);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn merge_prefers_reserved_on_overlap() {
+        let a = [entry(MemoryMapEntryKind::USABLE, 0, 0x4000)];
+        let b = [
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x4000, 0x1000),
+        ];
+
+        let merged = merge_memory_maps(&a, &b);
+        assert_eq!(
+            merged,
+            [
+                entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+                entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+                entry(MemoryMapEntryKind::USABLE, 0x2000, 0x3000),
+            ]
+        );
+        assert_eq!(validate_memory_map(&merged), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn builder_punches_in_reservations() {
+        let map = MapBuilder::new()
+            .region(0, 0x10_0000, MemoryMapEntryKind::USABLE)
+            .reserve(0x4_0000, 0x2_0000, MemoryMapEntryKind::KERNEL)
+            .build();
+
+        assert_eq!(
+            map,
+            Ok(std::vec![
+                entry(MemoryMapEntryKind::USABLE, 0, 0x4_0000),
+                entry(MemoryMapEntryKind::KERNEL, 0x4_0000, 0x2_0000),
+                entry(MemoryMapEntryKind::USABLE, 0x6_0000, 0xa_0000),
+            ])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn builder_rejects_unaligned_reservations() {
+        let map = MapBuilder::new()
+            .region(0, 0x10_0000, MemoryMapEntryKind::USABLE)
+            .reserve(0x4_0800, 0x1000, MemoryMapEntryKind::MODULE)
+            .build();
+
+        assert_eq!(map, Err(MemoryMapError::Unaligned { index: 0 }));
+    }
+
+    #[test]
+    fn clip_to_window_truncates_entries() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x2000),
+            entry(MemoryMapEntryKind::RESERVED, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x3000, 0x3000),
+            entry(MemoryMapEntryKind::KERNEL, 0x6000, 0x1000),
+        ];
+        let mut out = [entry(MemoryMapEntryKind::USABLE, 0, 0); 4];
+
+        assert_eq!(clip_to_window(&entries, 0x1000..0x4000, &mut out), Ok(3));
+        assert_eq!(
+            out[..3],
+            [
+                entry(MemoryMapEntryKind::USABLE, 0x1000, 0x1000),
+                entry(MemoryMapEntryKind::RESERVED, 0x2000, 0x1000),
+                entry(MemoryMapEntryKind::USABLE, 0x3000, 0x1000),
+            ]
+        );
+        assert_eq!(
+            clip_to_window(&entries, 0..0x7000, &mut out[..2]),
+            Err(MemoryMapError::BufferTooSmall { index: 2 })
+        );
+    }
+
+    #[test]
+    fn one_arena_per_usable_region() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x3000),
+            entry(MemoryMapEntryKind::USABLE, 0x8000, 0x2000),
+        ];
+
+        let arenas = usable_regions_as_arenas(&entries).collect::<std::vec::Vec<_>>();
+        assert_eq!(
+            arenas,
+            [
+                Arena {
+                    base: 0,
+                    size: 0x1000
+                },
+                Arena {
+                    base: 0x2000,
+                    size: 0x3000
+                },
+                Arena {
+                    base: 0x8000,
+                    size: 0x2000
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn index_matches_linear_search() {
+        let entries = [
+            entry(MemoryMapEntryKind::RESERVED, 0x3000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x6000, 0x2000),
+            entry(MemoryMapEntryKind::KERNEL, 0x4000, 0x1000),
+        ];
+        let index = MemoryMapIndex::new(&entries);
+
+        for address in (0..0x9000).step_by(0x400) {
+            assert_eq!(
+                index.region_containing(address),
+                region_containing(&entries, address)
+            );
+            assert_eq!(
+                index.kind_at(address),
+                region_containing(&entries, address).map(|entry| entry.kind)
+            );
+        }
+
+        assert_eq!(index.nearest_usable(0x1000), Some(&entries[1]));
+        assert_eq!(index.nearest_usable(0x3800), Some(&entries[1]));
+        assert_eq!(index.nearest_usable(0x4800), Some(&entries[2]));
+        assert_eq!(index.nearest_usable(0x10000), Some(&entries[2]));
+    }
+
+    /// Returns the bytes backing `entries`.
+    fn entry_bytes(entries: &[MemoryMapEntry]) -> &[u8] {
+        // SAFETY: `entries` is valid for reads of `size_of_val(entries)` bytes, and
+        // `MemoryMapEntry` has no padding.
+        unsafe {
+            core::slice::from_raw_parts(entries.as_ptr().cast(), core::mem::size_of_val(entries))
+        }
+    }
+
+    #[test]
+    fn parse_memory_map_round_trip() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::KERNEL, 0x1000, 0x2000),
+        ];
+        assert_eq!(parse_memory_map(entry_bytes(&entries)), Ok(&entries[..]));
+        assert_eq!(parse_memory_map(&[]), Ok(&[][..]));
+    }
+
+    #[test]
+    fn parse_memory_map_truncated() {
+        let entries = [entry(MemoryMapEntryKind::USABLE, 0, 0x1000)];
+        let bytes = entry_bytes(&entries);
+        assert_eq!(
+            parse_memory_map(&bytes[..bytes.len() - 1]),
+            Err(ParseError::BadLength)
+        );
+    }
+
+    #[test]
+    fn parse_memory_map_misaligned() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::KERNEL, 0x1000, 0x2000),
+        ];
+        let bytes = entry_bytes(&entries);
+        let size = core::mem::size_of::<MemoryMapEntry>();
+        assert_eq!(
+            parse_memory_map(&bytes[1..=size]),
+            Err(ParseError::Misaligned)
+        );
+        // An empty blob holds no entries to misread, so its alignment does not matter.
+        assert_eq!(parse_memory_map(&bytes[1..1]), Ok(&[][..]));
+    }
+
+    #[test]
+    fn unaccepted_batches_split_coalesced_runs() {
+        let entries = [
+            entry(MemoryMapEntryKind::UNACCEPTED, 0, 0x3000),
+            entry(MemoryMapEntryKind::UNACCEPTED, 0x3000, 0x2000),
+            entry(MemoryMapEntryKind::UNACCEPTED, 0x5000, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x7000, 0x1000),
+            entry(MemoryMapEntryKind::UNACCEPTED, 0x8000, 0x1000),
+        ];
+
+        assert_eq!(
+            unaccepted_batches(&entries, 0x4000).collect::<std::vec::Vec<_>>(),
+            [0..0x4000, 0x4000..0x7000, 0x8000..0x9000]
+        );
+        assert_eq!(
+            unaccepted_batches(&entries, 0).collect::<std::vec::Vec<_>>(),
+            [0..0x7000, 0x8000..0x9000]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn dt_reg_encodes_two_cell_region() {
+        let entries = [
+            entry(MemoryMapEntryKind::RESERVED, 0, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x1_2345_6000, 0x8000_0000),
+        ];
+
+        assert_eq!(
+            memory_map_to_dt_reg(&entries, 2, 2),
+            [
+                0x00, 0x00, 0x00, 0x01, 0x23, 0x45, 0x60, 0x00, //
+                0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn total_span_uses_highest_end() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0x1000, 0x10_0000),
+            entry(MemoryMapEntryKind::RESERVED, 0x8000, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, u64::MAX - 0x1000, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x20_0000, 0x1000),
+        ];
+
+        assert_eq!(total_span(&entries[..2]), Some(0x1000..0x10_1000));
+        assert_eq!(total_span(&entries), Some(0x1000..u64::MAX));
+        assert_eq!(total_span(&[]), None);
+    }
+
+    #[test]
+    fn memory_map_wrapper_delegates() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x3000),
+        ];
+        let map = MemoryMap::new(&entries);
+
+        assert_eq!(map.usable().count(), 2);
+        assert_eq!(map.into_iter().count(), 3);
+        assert_eq!(map.total_usable_bytes(), 0x4000);
+        assert_eq!(map.region_containing(0x1800), Some(&entries[1]));
+        assert_eq!(map.validate(), validate_memory_map(&entries));
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn trampoline_page_is_aligned_low_memory() {
+        let entries = [
+            entry(MemoryMapEntryKind::RESERVED, 0, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x1800, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x8800, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x10_0000, 0x10_0000),
+        ];
+        assert_eq!(find_trampoline_page(&entries), Some(0x9000));
+
+        let entries = [
+            entry(MemoryMapEntryKind::RESERVED, 0, 0xf_f000),
+            entry(MemoryMapEntryKind::USABLE, 0xf_f800, 0x10_0000),
+        ];
+        assert_eq!(find_trampoline_page(&entries), None);
+    }
+
+    #[test]
+    fn distinct_kinds_counts_each_kind_once() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::KERNEL, 0x3000, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x4000, 0x1000),
+        ];
+        assert_eq!(distinct_kinds(&entries), 3);
+        assert_eq!(distinct_kinds(&[]), 0);
+    }
+
+    #[test]
+    fn largest_gap_picks_the_widest() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x8000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x9000, 0x1000),
+        ];
+        assert_eq!(largest_gap(&entries), Some(0x3000..0x8000));
+        assert_eq!(largest_gap(&entries[2..]), None);
+    }
+
+    #[test]
+    fn bump_region_takes_the_first_fit() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x10_0000),
+            entry(MemoryMapEntryKind::USABLE, 0x10_1000, 0x4000),
+            entry(MemoryMapEntryKind::USABLE, 0x20_0000, 0x10_0000),
+        ];
+        assert_eq!(bump_region(&entries, 0x2000), Some((0x10_1000, 0x4000)));
+        assert_eq!(bump_region(&entries, 0x1000), Some((0, 0x1000)));
+        assert_eq!(bump_region(&entries, 0x20_0000), None);
+    }
+
+    #[test]
+    fn usable_bytes_above_clips_straddling_regions() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x9_f000),
+            entry(MemoryMapEntryKind::USABLE, 0xf_0000, 0x2_0000),
+            entry(MemoryMapEntryKind::RESERVED, 0x11_0000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x20_0000, 0x1000),
+        ];
+        assert_eq!(usable_bytes_above(&entries, 0x10_0000), 0x1_1000);
+        assert_eq!(usable_bytes_above(&entries, 0), 0x9_f000 + 0x2_1000);
+        assert_eq!(usable_bytes_above(&entries, u64::MAX), 0);
+    }
+
+    #[test]
+    fn huge_page_ranges_cover_reserved_regions() {
+        const PAGE: u64 = 2 << 20;
+
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x10_0000),
+            entry(MemoryMapEntryKind::RESERVED, 0x10_0000, 0x1000),
+            entry(MemoryMapEntryKind::ACPI_RECLAIMABLE, 0x30_0000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x30_1000, 0x100_0000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1_3fff_f000, 0x2000),
+        ];
+
+        assert_eq!(
+            huge_page_ranges(&entries, PAGE).collect::<std::vec::Vec<_>>(),
+            [0..2 * PAGE, 0x1_3fe0_0000..0x1_4020_0000]
+        );
+    }
+
+    #[test]
+    fn entries_key_a_btree_map_by_base() {
+        use std::collections::BTreeMap;
+
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0, 0x1000),
+            entry(MemoryMapEntryKind::KERNEL, 0x1000, 0x1000),
+        ];
+
+        let by_base = entries
+            .iter()
+            .map(|entry| (entry.base_key(), *entry))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            by_base.keys().copied().collect::<std::vec::Vec<_>>(),
+            [0, 0x1000, 0x2000]
+        );
+        assert_eq!(by_base.get(&0x1000), Some(&entries[2]));
+
+        let by_key = entries
+            .iter()
+            .map(|entry| (RegionKey::from(entry), *entry))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(by_key.get(&0x2000), Some(&entries[0]));
+        assert_eq!(
+            by_key.range(..0x1800).next_back(),
+            Some((&RegionKey(0x1000), &entries[2]))
+        );
+    }
+
+    #[test]
+    fn validated_iter_stops_at_third_entry() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x2800, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x4000, 0x1000),
+        ];
+
+        let mut iter = validated_iter(&entries);
+        assert_eq!(iter.next(), Some(Ok(&entries[0])));
+        assert_eq!(iter.next(), Some(Ok(&entries[1])));
+        assert_eq!(
+            iter.next(),
+            Some(Err(MemoryMapError::Unaligned { index: 2 }))
+        );
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn is_coalesced_detects_mergeable_neighbours() {
+        let coalesced = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x4000, 0x1000),
+        ];
+        assert!(is_coalesced(&coalesced));
+
+        let mergeable = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x1000, 0x1000),
+        ];
+        assert!(!is_coalesced(&mergeable));
+    }
+
+    #[test]
+    fn zero_size_entries_are_rejected() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x1000),
+        ];
+        assert!(has_zero_size_entries(&entries));
+        assert_eq!(
+            validate_memory_map(&entries),
+            Err(MemoryMapError::ZeroSize { index: 1 })
+        );
+
+        assert!(!has_zero_size_entries(&[entries[0], entries[2]]));
+        assert_eq!(validate_memory_map(&[entries[0], entries[2]]), Ok(()));
+    }
+
+    #[test]
+    fn regions_bracket_a_gap() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x8000, 0x1000),
+        ];
+
+        assert_eq!(region_before(&entries, 0x4000), Some(&entries[1]));
+        assert_eq!(region_after(&entries, 0x4000), Some(&entries[2]));
+        assert_eq!(region_before(&entries, 0x2000), Some(&entries[1]));
+        assert_eq!(region_before(&entries, 0x800), None);
+        assert_eq!(region_after(&entries, 0x8800), None);
+    }
+
+    #[test]
+    fn permanently_reserved_excludes_reclaimable() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::RESERVED, 0x1000, 0x1000),
+            entry(MemoryMapEntryKind::ACPI_RECLAIMABLE, 0x2000, 0x2000),
+            entry(MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE, 0x4000, 0x1000),
+            entry(MemoryMapEntryKind::BOOTLOADER, 0x5000, 0x4000),
+            entry(MemoryMapEntryKind::KERNEL, 0x9000, 0x2000),
+            entry(MemoryMapEntryKind::MODULE, 0xb000, 0x1000),
+            entry(MemoryMapEntryKind::UNUSABLE, 0xc000, 0x1000),
+        ];
+        assert_eq!(permanently_reserved_bytes(&entries), 0x6000);
+    }
+
+    #[test]
+    fn is_range_usable_spans_adjacent_usable_regions() {
+        let entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x2000),
+            entry(MemoryMapEntryKind::USABLE, 0x2000, 0x2000),
+            entry(MemoryMapEntryKind::RESERVED, 0x4000, 0x1000),
+            entry(MemoryMapEntryKind::USABLE, 0x5000, 0x1000),
+        ];
+
+        assert!(is_range_usable(&entries, 0x800..0x1800));
+        assert!(is_range_usable(&entries, 0x1000..0x3000));
+        assert!(!is_range_usable(&entries, 0x3000..0x4800));
+        assert!(!is_range_usable(&entries, 0x5800..0x6800));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn layout_signature_ignores_usable_regions() {
+        let boot = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x9_f000),
+            entry(MemoryMapEntryKind::RESERVED, 0x9_f000, 0x6_1000),
+            entry(MemoryMapEntryKind::USABLE, 0x10_0000, 0x7ff0_0000),
+        ];
+        let mut usable_shifted = boot;
+        usable_shifted[2].size -= 0x10_0000;
+        let mut reserved_shifted = boot;
+        reserved_shifted[1].size += 0x1000;
+
+        assert_eq!(layout_signature(&boot), layout_signature(&usable_shifted));
+        assert_ne!(layout_signature(&boot), layout_signature(&reserved_shifted));
+    }
+}
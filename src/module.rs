@@ -0,0 +1,416 @@
+//! Helpers for inspecting the [`ModuleEntry`]s passed through the [`BootloaderResponse`].
+//!
+//! [`BootloaderResponse`]: crate::BootloaderResponse
+
+use core::str::Utf8Error;
+
+use crate::{
+    fields::FieldMask, memory_map::FRAME_SIZE, BootloaderResponse, ModuleEntry, ModuleKind,
+};
+
+/// Various errors that can occur when validating a list of [`ModuleEntry`]s.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ModuleError {
+    /// The module at `index` has a non-zero name length but a NULL name.
+    NullName {
+        /// The index of the offending module.
+        index: usize,
+    },
+    /// The module at `index` has a non-zero size but a NULL address.
+    NullAddress {
+        /// The index of the offending module.
+        index: usize,
+    },
+    /// The module at `index` has an address that is not 4096 byte aligned.
+    Unaligned {
+        /// The index of the offending module.
+        index: usize,
+    },
+    /// The module at `index` has a size of 0.
+    ZeroSize {
+        /// The index of the offending module.
+        index: usize,
+    },
+    /// The module at `index` extends past the end of the address space.
+    Overflow {
+        /// The index of the offending module.
+        index: usize,
+    },
+}
+
+impl core::fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NullName { index } => write!(f, "module {index} has a NULL name"),
+            Self::NullAddress { index } => write!(f, "module {index} has a NULL address"),
+            Self::Unaligned { index } => write!(f, "module {index} is not 4096 byte aligned"),
+            Self::ZeroSize { index } => write!(f, "module {index} is empty"),
+            Self::Overflow { index } => {
+                write!(
+                    f,
+                    "module {index} extends past the end of the address space"
+                )
+            }
+        }
+    }
+}
+
+/// Validates that every pointer in `modules` is non-NULL whenever its length is non-zero.
+///
+/// # Errors
+/// Returns the [`ModuleError`] describing the first inconsistent module.
+pub fn validate_module_pointers(modules: &[ModuleEntry]) -> Result<(), ModuleError> {
+    for (index, module) in modules.iter().enumerate() {
+        if module.name.is_null() && module.name_length != 0 {
+            return Err(ModuleError::NullName { index });
+        }
+        if module.address.is_null() && module.size != 0 {
+            return Err(ModuleError::NullAddress { index });
+        }
+    }
+
+    Ok(())
+}
+
+/// Sorts `modules` by address, lowest to highest.
+///
+/// The relative order of modules with the same address is unspecified.
+pub fn sort_modules_by_address(modules: &mut [ModuleEntry]) {
+    modules.sort_unstable_by_key(|module| module.address);
+}
+
+/// Removes every module in `modules` with the same address as the module preceding it,
+/// compacting the remaining modules to the start of `modules` and returning their count.
+///
+/// `modules` must be sorted by address, see [`sort_modules_by_address`], so that duplicates
+/// are adjacent. The modules past the returned count are left in an unspecified order.
+pub fn dedup_modules(modules: &mut [ModuleEntry]) -> usize {
+    if modules.is_empty() {
+        return 0;
+    }
+
+    let mut count = 1;
+    for index in 1..modules.len() {
+        if modules[index].address != modules[count - 1].address {
+            modules.swap(count, index);
+            count += 1;
+        }
+    }
+
+    count
+}
+
+impl BootloaderResponse {
+    /// Returns an iterator over the modules in ascending order of address, without modifying
+    /// the module array.
+    ///
+    /// Modules with the same address are yielded in their original order. No additional
+    /// storage is used, so there is no limit on the number of modules, but iterating over all
+    /// of them takes time quadratic in the number of modules.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules()`] apply.
+    pub unsafe fn modules_by_address(&self) -> impl Iterator<Item = &ModuleEntry> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let modules = unsafe { self.modules() };
+
+        let mut previous = None::<(*const u8, usize)>;
+        core::iter::from_fn(move || {
+            let (index, module) = modules
+                .iter()
+                .enumerate()
+                .filter(|&(index, module)| previous.is_none_or(|key| (module.address, index) > key))
+                .min_by_key(|&(index, module)| (module.address, index))?;
+
+            previous = Some((module.address, index));
+            Some(module)
+        })
+    }
+
+    /// Returns an iterator over the modules, each paired with the result of decoding its name.
+    ///
+    /// Unlike calling [`ModuleEntry::name()`] in a loop, a module with a name that is not valid
+    /// utf-8 does not prevent the remaining modules from being inspected.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules()`] and [`ModuleEntry::name()`]
+    /// apply to every module.
+    pub unsafe fn modules_named(
+        &self,
+    ) -> impl Iterator<Item = (Result<&str, Utf8Error>, &ModuleEntry)> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let modules = unsafe { self.modules() };
+
+        modules.iter().map(|module| {
+            // SAFETY: the caller upholds the requirements of `ModuleEntry::name()`.
+            (unsafe { module.name() }, module)
+        })
+    }
+
+    /// Returns the [`ModuleKind`] of `module`, or [`ModuleKind::UNKNOWN`] if this response
+    /// predates [`FieldMask::MODULE_KINDS`].
+    pub const fn module_kind(&self, module: &ModuleEntry) -> ModuleKind {
+        if self.has_fields(FieldMask::MODULE_KINDS) {
+            module.kind()
+        } else {
+            ModuleKind::UNKNOWN
+        }
+    }
+
+    /// Returns an iterator over the modules of the given [`ModuleKind`], in their original
+    /// order.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules()`] apply.
+    pub unsafe fn modules_of_kind(&self, kind: ModuleKind) -> impl Iterator<Item = &ModuleEntry> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let modules = unsafe { self.modules() };
+
+        modules
+            .iter()
+            .filter(move |module| self.module_kind(module) == kind)
+    }
+
+    /// Returns the first module of the given [`ModuleKind`], if any.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules()`] apply.
+    pub unsafe fn first_module_of_kind(&self, kind: ModuleKind) -> Option<&ModuleEntry> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        unsafe { self.modules_of_kind(kind) }.next()
+    }
+
+    /// Validates that every module is 4096 byte aligned, non-empty, and does not extend past
+    /// the end of the address space.
+    ///
+    /// Once this succeeds, [`ModuleEntry::data_pages()`] yields every byte of each module.
+    ///
+    /// # Errors
+    /// - [`ModuleError::Unaligned`]: a module's address is not 4096 byte aligned.
+    /// - [`ModuleError::ZeroSize`]: a module's size is 0.
+    /// - [`ModuleError::Overflow`]: a module's address plus its size overflows.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules()`] apply.
+    pub unsafe fn validate_modules(&self) -> Result<(), ModuleError> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let modules = unsafe { self.modules() };
+
+        for (index, module) in modules.iter().enumerate() {
+            let address = module.address as usize;
+            if !(address as u64).is_multiple_of(FRAME_SIZE) {
+                return Err(ModuleError::Unaligned { index });
+            }
+            if module.size == 0 {
+                return Err(ModuleError::ZeroSize { index });
+            }
+            if address.checked_add(module.size).is_none() {
+                return Err(ModuleError::Overflow { index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the data of the first module of kind [`ModuleKind::INITRD`], or, failing that,
+    /// of the first module named `"initrd"`, if any.
+    ///
+    /// The fallback to the name covers responses that predate [`FieldMask::MODULE_KINDS`]
+    /// and bootloaders that do not assign kinds.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::modules_named()`] and
+    /// [`ModuleEntry::data()`] apply.
+    pub unsafe fn initrd(&self) -> Option<&[u8]> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::modules()`.
+        let module = match unsafe { self.first_module_of_kind(ModuleKind::INITRD) } {
+            Some(module) => module,
+            // SAFETY: the caller upholds the requirements of
+            // `BootloaderResponse::modules_named()`.
+            None => unsafe { self.modules_named() }
+                .find(|(name, _)| *name == Ok("initrd"))
+                .map(|(_, module)| module)?,
+        };
+
+        // SAFETY: the caller upholds the requirements of `ModuleEntry::data()`.
+        Some(unsafe { module.data() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{module, response, set_modules};
+
+    #[test]
+    fn modules_by_address_is_ascending() {
+        let mut modules = [
+            module("c", 0x3000 as *const u8, 0x1000),
+            module("a", 0x1000 as *const u8, 0x1000),
+            module("d", 0x3000 as *const u8, 0x1000),
+            module("b", 0x2000 as *const u8, 0x1000),
+        ];
+        let mut response = response();
+        set_modules(&mut response, &mut modules);
+
+        // SAFETY: the modules point to `modules`, which outlives `response`.
+        let names = unsafe { response.modules_by_address() }
+            // SAFETY: every module name is a string literal.
+            .map(|module| unsafe { module.name() }.unwrap())
+            .collect::<std::vec::Vec<_>>();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+        assert_eq!(modules[0].address, 0x3000 as *const u8);
+    }
+
+    #[test]
+    fn modules_named_keeps_going_past_invalid_names() {
+        let invalid = [b'k', 0xff, b'm'];
+        let mut modules = [
+            module("", 0x2000 as *const u8, 0x1000),
+            module("initrd", 0x1000 as *const u8, 0x1000),
+        ];
+        modules[0].name = invalid.as_ptr();
+        modules[0].name_length = invalid.len();
+        let mut response = response();
+        set_modules(&mut response, &mut modules);
+
+        // SAFETY: the modules and their names point into locals that outlive `response`.
+        let mut named = unsafe { response.modules_named() };
+        let (name, module) = named.next().unwrap();
+        assert!(name.is_err());
+        assert_eq!(module.address, 0x2000 as *const u8);
+        let (name, module) = named.next().unwrap();
+        assert_eq!(name, Ok("initrd"));
+        assert_eq!(module.address, 0x1000 as *const u8);
+        assert!(named.next().is_none());
+    }
+
+    #[test]
+    fn modules_of_kind_filters_by_kind() {
+        let mut modules = [
+            module("ucode", 0x1000 as *const u8, 0x1000),
+            module("initrd", 0x2000 as *const u8, 0x1000),
+            module("dtb", 0x3000 as *const u8, 0x1000),
+            module("ucode-2", 0x4000 as *const u8, 0x1000),
+        ];
+        modules[0].flags = ModuleKind::MICROCODE.to_flags();
+        modules[1].flags = ModuleKind::INITRD.to_flags() | crate::MODULE_WRITABLE;
+        modules[2].flags = ModuleKind::DEVICE_TREE.to_flags();
+        modules[3].flags = ModuleKind::MICROCODE.to_flags();
+        let mut response = response();
+        set_modules(&mut response, &mut modules);
+
+        // SAFETY: the modules point to `modules`, which outlives `response`.
+        unsafe {
+            let microcode = response
+                .modules_of_kind(ModuleKind::MICROCODE)
+                .map(|module| module.address)
+                .collect::<std::vec::Vec<_>>();
+            assert_eq!(microcode, [0x1000 as *const u8, 0x4000 as *const u8]);
+            assert_eq!(
+                response.first_module_of_kind(ModuleKind::MICROCODE),
+                Some(&modules[0])
+            );
+            assert_eq!(
+                response.first_module_of_kind(ModuleKind::INITRD),
+                Some(&modules[1])
+            );
+            assert_eq!(response.first_module_of_kind(ModuleKind::UNKNOWN), None);
+
+            // Before module kinds were introduced, the upper bits of the flags are not
+            // meaningful.
+            response.api_version = 13;
+            assert_eq!(response.first_module_of_kind(ModuleKind::INITRD), None);
+            assert_eq!(
+                response.first_module_of_kind(ModuleKind::UNKNOWN),
+                Some(&modules[0])
+            );
+        }
+    }
+
+    #[test]
+    fn validate_modules_reports_offending_index() {
+        fn validate(modules: &mut [ModuleEntry]) -> Result<(), ModuleError> {
+            let mut response = response();
+            set_modules(&mut response, modules);
+            // SAFETY: the modules point to `modules`, which outlives `response`.
+            unsafe { response.validate_modules() }
+        }
+
+        let top = (usize::MAX & !0xfff) as *const u8;
+        let mut modules = [
+            module("a", 0x1000 as *const u8, 0x1000),
+            module("b", 0x2800 as *const u8, 0x1000),
+        ];
+        assert_eq!(
+            validate(&mut modules),
+            Err(ModuleError::Unaligned { index: 1 })
+        );
+
+        modules[1] = module("b", top, 0x2000);
+        assert_eq!(
+            validate(&mut modules),
+            Err(ModuleError::Overflow { index: 1 })
+        );
+
+        modules[1] = module("b", top, 0);
+        assert_eq!(
+            validate(&mut modules),
+            Err(ModuleError::ZeroSize { index: 1 })
+        );
+
+        modules[1] = module("b", top, 0x800);
+        assert_eq!(validate(&mut modules), Ok(()));
+    }
+
+    #[test]
+    fn dedup_modules_keeps_the_first_duplicate() {
+        let mut modules = [
+            module("b", 0x2000 as *const u8, 0x1000),
+            module("a", 0x1000 as *const u8, 0x1000),
+            module("a-again", 0x1000 as *const u8, 0x1000),
+            module("c", 0x3000 as *const u8, 0x1000),
+        ];
+        sort_modules_by_address(&mut modules);
+        let first = modules[0];
+
+        assert_eq!(dedup_modules(&mut modules), 3);
+        assert_eq!(modules[0], first);
+        assert_eq!(
+            modules[..3]
+                .iter()
+                .map(|module| module.address)
+                .collect::<std::vec::Vec<_>>(),
+            [
+                0x1000 as *const u8,
+                0x2000 as *const u8,
+                0x3000 as *const u8
+            ]
+        );
+        assert_eq!(dedup_modules(&mut []), 0);
+    }
+
+    #[test]
+    fn initrd_prefers_kind_over_name() {
+        let (named, kinded) = ([1u8; 4], [2u8; 8]);
+        let mut modules = [
+            module("kernel.sym", 0x1000 as *const u8, 0x1000),
+            module("initrd", named.as_ptr(), named.len()),
+            module("rootfs.img", kinded.as_ptr(), kinded.len()),
+        ];
+        let mut response = response();
+
+        set_modules(&mut response, &mut modules[..1]);
+        // SAFETY: the modules point into `modules`, whose data is never read.
+        assert_eq!(unsafe { response.initrd() }, None);
+
+        set_modules(&mut response, &mut modules[..2]);
+        // SAFETY: the modules point into `modules`, and the initrd data to `named`.
+        assert_eq!(unsafe { response.initrd() }, Some(&named[..]));
+
+        modules[2].flags = ModuleKind::INITRD.to_flags();
+        set_modules(&mut response, &mut modules);
+        // SAFETY: the modules point into `modules`, and the initrd data to `kinded`.
+        assert_eq!(unsafe { response.initrd() }, Some(&kinded[..]));
+    }
+}
@@ -0,0 +1,242 @@
+//! Self-contained snapshots of a [`BootloaderResponse`] that do not reference firmware memory.
+
+use alloc::{string::String, vec::Vec};
+use core::str::Utf8Error;
+
+use crate::{
+    address::{DirectMapOffset, VirtAddr},
+    fields::FieldMask,
+    BootloaderResponse, MemoryMapEntry, ModuleEntry,
+};
+
+/// An owned copy of a [`ModuleEntry`] and its data.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OwnedModule {
+    /// The name of the module.
+    pub name: String,
+    /// A copy of the data of the module.
+    pub data: Vec<u8>,
+    /// The value of [`ModuleEntry::flags`].
+    pub flags: u64,
+}
+
+impl OwnedModule {
+    /// Creates a new [`OwnedModule`] by deep copying `module`.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the name of `module` is not valid utf-8.
+    ///
+    /// # Safety
+    /// The same requirements as [`ModuleEntry::name()`] and [`ModuleEntry::data()`] apply.
+    pub unsafe fn new(module: &ModuleEntry) -> Result<Self, Utf8Error> {
+        // SAFETY: the caller upholds the requirements of `ModuleEntry::name()` and
+        // `ModuleEntry::data()`.
+        let (name, data) = unsafe { (module.name()?, module.data()) };
+
+        Ok(Self {
+            name: String::from(name),
+            data: data.to_vec(),
+            flags: module.flags,
+        })
+    }
+}
+
+/// An owned snapshot of a [`BootloaderResponse`].
+///
+/// Version-gated fields hold the values returned by their [`BootloaderResponse`] accessors.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct OwnedResponse {
+    /// The value of [`BootloaderResponse::api_version`].
+    pub api_version: u64,
+    /// The name of the loading bootloader.
+    pub bootloader_name: String,
+    /// The version of the loading bootloader.
+    pub bootloader_version: String,
+    /// The [`PROTOCOL_REVISION`](crate::PROTOCOL_REVISION) the loading bootloader was built
+    /// against.
+    pub protocol_revision: String,
+    /// The name of the firmware vendor.
+    pub firmware_vendor: String,
+    /// The virtual address of the base of the kernel.
+    pub kernel_virtual_address: VirtAddr,
+    /// The virtual address of the kernel entry point.
+    pub kernel_entry: VirtAddr,
+    /// The value of [`BootloaderResponse::kaslr_slide`].
+    pub kaslr_slide: i64,
+    /// The offset of the higher half direct mapped memory.
+    pub direct_map: DirectMapOffset,
+    /// The value of [`BootloaderResponse::paging_levels`].
+    pub paging_levels: u8,
+    /// The value of [`BootloaderResponse::capabilities`].
+    pub capabilities: u64,
+    /// The value of [`BootloaderResponse::page_table_root`].
+    pub page_table_root: u64,
+    /// Whether UEFI runtime services may be called.
+    pub uefi_runtime_available: bool,
+    /// The value of [`BootloaderResponse::rsdp_physical()`].
+    pub rsdp_physical_address: Option<u64>,
+    /// The value of [`BootloaderResponse::kernel_physical_address`].
+    pub kernel_physical_address: u64,
+    /// The value of [`BootloaderResponse::kernel_size`].
+    pub kernel_size: u64,
+    /// A copy of the memory map.
+    pub memory_map: Vec<MemoryMapEntry>,
+    /// Copies of the modules.
+    pub modules: Vec<OwnedModule>,
+}
+
+impl BootloaderResponse {
+    /// Deep copies this [`BootloaderResponse`], including its memory map, modules, and
+    /// strings, into an [`OwnedResponse`].
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if any string is not valid utf-8.
+    ///
+    /// # Safety
+    /// Every pointer in `self` must uphold the requirements of the corresponding
+    /// [`BootloaderResponse`] accessor, and every module must uphold the requirements of
+    /// [`OwnedModule::new()`].
+    pub unsafe fn to_owned_response(&self) -> Result<OwnedResponse, Utf8Error> {
+        // SAFETY: the caller guarantees that every pointer in `self` is valid.
+        let (bootloader_name, bootloader_version, protocol_revision, firmware_vendor) = unsafe {
+            (
+                self.bootloader_name_owned()?,
+                self.bootloader_version_owned()?,
+                String::from(self.protocol_revision()?),
+                String::from(self.firmware_vendor()?),
+            )
+        };
+        // SAFETY: the caller guarantees that the memory map and module array are valid.
+        let (memory_map, modules) = unsafe { (self.memory_map().to_vec(), self.modules()) };
+
+        Ok(OwnedResponse {
+            api_version: self.api_version,
+            bootloader_name,
+            bootloader_version,
+            protocol_revision,
+            firmware_vendor,
+            kernel_virtual_address: self.kernel_virtual_address(),
+            kernel_entry: self.kernel_entry(),
+            kaslr_slide: self.kaslr_slide(),
+            direct_map: self.direct_map(),
+            paging_levels: self.paging_levels(),
+            capabilities: self.capabilities,
+            page_table_root: self.page_table_root(),
+            uefi_runtime_available: self.uefi_runtime_available(),
+            rsdp_physical_address: self.rsdp_physical(),
+            kernel_physical_address: self
+                .gated(FieldMask::KERNEL_PHYSICAL, self.kernel_physical_address),
+            kernel_size: self.gated(FieldMask::KERNEL_PHYSICAL, self.kernel_size),
+            memory_map,
+            modules: modules
+                .iter()
+                // SAFETY: the caller upholds the requirements of `OwnedModule::new()`.
+                .map(|module| unsafe { OwnedModule::new(module) })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{vec, vec::Vec};
+
+    use super::*;
+    use crate::test_util::{entry, response, set_memory_map, set_modules};
+
+    /// Returns a [`BootloaderResponse`] that borrows the strings of `owned`, with the memory map
+    /// and modules pointing to `memory_map` and `modules`.
+    fn borrow(
+        owned: &OwnedResponse,
+        memory_map: &mut [MemoryMapEntry],
+        modules: &mut [ModuleEntry],
+    ) -> BootloaderResponse {
+        let mut response = response();
+        response.api_version = owned.api_version;
+        response.bootloader_name = owned.bootloader_name.as_ptr();
+        response.bootloader_name_length = owned.bootloader_name.len();
+        response.bootloader_version = owned.bootloader_version.as_ptr();
+        response.bootloader_version_length = owned.bootloader_version.len();
+        response.protocol_revision = owned.protocol_revision.as_ptr();
+        response.protocol_revision_length = owned.protocol_revision.len();
+        response.firmware_vendor = owned.firmware_vendor.as_ptr();
+        response.firmware_vendor_length = owned.firmware_vendor.len();
+        response.kernel_virtual_address = owned.kernel_virtual_address.as_usize() as *const _;
+        response.kernel_entry = owned.kernel_entry.as_usize() as *const _;
+        response.kaslr_slide = owned.kaslr_slide;
+        response.direct_map = owned.direct_map.0;
+        response.paging_levels = owned.paging_levels;
+        response.capabilities = owned.capabilities;
+        response.page_table_root = owned.page_table_root;
+        response.uefi_runtime_available = owned.uefi_runtime_available.into();
+        response.kernel_physical_address = owned.kernel_physical_address;
+        response.kernel_size = owned.kernel_size;
+        set_memory_map(&mut response, memory_map);
+        set_modules(&mut response, modules);
+        response
+    }
+
+    #[test]
+    fn borrowed_owned_response_round_trips() {
+        let name = "initrd";
+        let data = [0xaa_u8; 16];
+        let mut modules = [ModuleEntry {
+            name: name.as_ptr(),
+            name_length: name.len(),
+            address: data.as_ptr(),
+            size: data.len(),
+            flags: crate::MODULE_WRITABLE,
+        }];
+        let mut entries = [
+            entry(crate::MemoryMapEntryKind::USABLE, 0, 0x10_0000),
+            entry(crate::MemoryMapEntryKind::KERNEL, 0x10_0000, 0x4000),
+        ];
+        let owned = OwnedResponse {
+            api_version: crate::API_VERSION,
+            bootloader_name: String::from("capora"),
+            bootloader_version: String::from("1.0"),
+            protocol_revision: String::from(crate::PROTOCOL_REVISION),
+            firmware_vendor: String::from("EDK II"),
+            kernel_virtual_address: VirtAddr::new(0xffff_ffff_8000_0000),
+            kernel_entry: VirtAddr::new(0xffff_ffff_8000_1000),
+            kaslr_slide: 0x20_0000,
+            direct_map: DirectMapOffset(0xffff_8000_0000_0000),
+            paging_levels: 4,
+            capabilities: crate::CAP_FRAMEBUFFER,
+            page_table_root: 0x5000,
+            uefi_runtime_available: true,
+            rsdp_physical_address: None,
+            kernel_physical_address: 0x10_0000,
+            kernel_size: 0x4000,
+            memory_map: entries.to_vec(),
+            modules: vec![OwnedModule {
+                name: String::from(name),
+                data: data.to_vec(),
+                flags: crate::MODULE_WRITABLE,
+            }],
+        };
+
+        let response = borrow(&owned, &mut entries, &mut modules);
+        // SAFETY: every pointer in `response` points into `owned` or a local, all of which
+        // outlive `response`.
+        let copy = unsafe { response.to_owned_response() }.unwrap();
+        assert_eq!(copy, owned);
+
+        let mut entries = copy.memory_map.clone();
+        let mut modules = copy
+            .modules
+            .iter()
+            .map(|module| ModuleEntry {
+                name: module.name.as_ptr(),
+                name_length: module.name.len(),
+                address: module.data.as_ptr(),
+                size: module.data.len(),
+                flags: module.flags,
+            })
+            .collect::<Vec<_>>();
+        let response = borrow(&copy, &mut entries, &mut modules);
+        // SAFETY: every pointer in `response` points into `copy` or a local, all of which
+        // outlive `response`.
+        assert_eq!(unsafe { response.to_owned_response() }, Ok(copy));
+    }
+}
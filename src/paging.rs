@@ -0,0 +1,239 @@
+//! Helpers for building the kernel's initial page tables from a [`BootloaderResponse`].
+
+use crate::{BootloaderResponse, Framebuffer, MemoryMapEntryKind};
+
+/// The permissions and caching attributes with which a region should be mapped.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MapFlags(pub u64);
+
+impl MapFlags {
+    /// The region should be mapped with no permissions.
+    pub const NONE: Self = Self(0);
+    /// The region should be readable.
+    pub const READ: Self = Self(1 << 0);
+    /// The region should be writable.
+    pub const WRITE: Self = Self(1 << 1);
+    /// The region should be executable.
+    pub const EXECUTE: Self = Self(1 << 2);
+    /// The region should be mapped with write-combining caching.
+    pub const WRITE_COMBINING: Self = Self(1 << 3);
+
+    /// Returns the union of `self` and `other`.
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns `true` if `self` contains every flag in `other`.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl core::ops::BitOr for MapFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+/// A region that the kernel must map before it can stop relying on the bootloader's mappings.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MapRequest {
+    /// The physical address of the region.
+    pub phys: u64,
+    /// The virtual address at which the region should be mapped.
+    pub virt: u64,
+    /// The size, in bytes, of the region.
+    pub size: u64,
+    /// The flags with which the region should be mapped.
+    pub flags: MapFlags,
+}
+
+impl BootloaderResponse {
+    /// Returns an iterator over the regions the kernel must map before it can stop relying on
+    /// the bootloader's mappings.
+    ///
+    /// [`MemoryMapEntryKind::KERNEL`] regions are mapped relative to
+    /// [`BootloaderResponse::kernel_virtual_address`], assuming the kernel image is laid out
+    /// virtually as it is physically. [`MemoryMapEntryKind::BOOTLOADER`] regions, which
+    /// contain the response, are mapped through [`BootloaderResponse::direct_map`]. Both use
+    /// [`MemoryMapEntryKind::default_permissions`]. Every [`Framebuffer`] is mapped at its
+    /// address, which lies within the direct map, with
+    /// [`Framebuffer::default_permissions`].
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::memory_map()`] and
+    /// [`BootloaderResponse::framebuffers()`] apply.
+    pub unsafe fn early_map_plan(&self) -> impl Iterator<Item = MapRequest> + '_ {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+        let memory_map = unsafe { self.memory_map() };
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::framebuffers()`.
+        let framebuffers = unsafe { self.framebuffers() };
+        let kernel_base = memory_map
+            .iter()
+            .filter(|entry| entry.kind == MemoryMapEntryKind::KERNEL)
+            .map(|entry| entry.base)
+            .min()
+            .unwrap_or(0);
+        let kernel_virtual_address = self.kernel_virtual_address as usize as u64;
+        let direct_map = self.direct_map();
+
+        let regions = memory_map.iter().filter_map(move |entry| {
+            let virt = match entry.kind {
+                MemoryMapEntryKind::KERNEL => {
+                    kernel_virtual_address.wrapping_add(entry.base - kernel_base)
+                }
+                MemoryMapEntryKind::BOOTLOADER => direct_map.phys_to_virt(entry.base) as u64,
+                _ => return None,
+            };
+
+            Some(MapRequest {
+                phys: entry.base,
+                virt,
+                size: entry.size,
+                flags: entry.kind.default_permissions(),
+            })
+        });
+        let framebuffers = framebuffers.iter().map(move |framebuffer| MapRequest {
+            phys: direct_map.virt_to_phys(framebuffer.address as usize),
+            virt: framebuffer.address as usize as u64,
+            size: framebuffer.pitch.saturating_mul(framebuffer.height),
+            flags: framebuffer.default_permissions(),
+        });
+
+        regions.chain(framebuffers)
+    }
+}
+
+impl Framebuffer {
+    /// Returns the conventional [`MapFlags`] with which a framebuffer is mapped: read, write,
+    /// and write-combining.
+    pub const fn default_permissions(&self) -> MapFlags {
+        MapFlags::READ
+            .union(MapFlags::WRITE)
+            .union(MapFlags::WRITE_COMBINING)
+    }
+}
+
+impl MemoryMapEntryKind {
+    /// Returns the conventional [`MapFlags`] with which memory of this kind is mapped.
+    ///
+    /// - [`USABLE`](Self::USABLE), [`BOOTLOADER`](Self::BOOTLOADER), and
+    ///   [`ACPI_NONVOLATILE_STORAGE`](Self::ACPI_NONVOLATILE_STORAGE): read and write.
+    /// - [`ACPI_RECLAIMABLE`](Self::ACPI_RECLAIMABLE) and [`MODULE`](Self::MODULE): read.
+    /// - [`KERNEL`](Self::KERNEL): read and execute.
+    /// - Any other kind: none.
+    ///
+    /// Framebuffers are not described by the memory map, see
+    /// [`Framebuffer::default_permissions`].
+    pub const fn default_permissions(&self) -> MapFlags {
+        match *self {
+            Self::USABLE | Self::BOOTLOADER | Self::ACPI_NONVOLATILE_STORAGE => {
+                MapFlags::READ.union(MapFlags::WRITE)
+            }
+            Self::ACPI_RECLAIMABLE | Self::MODULE => MapFlags::READ,
+            Self::KERNEL => MapFlags::READ.union(MapFlags::EXECUTE),
+            _ => MapFlags::NONE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::test_util::{entry, response, set_memory_map};
+
+    #[test]
+    fn plan_maps_kernel_and_framebuffer() {
+        const DIRECT_MAP: usize = 0xffff_8000_0000_0000;
+        const KERNEL: usize = 0xffff_ffff_8000_0000;
+
+        let mut entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x10_0000),
+            entry(MemoryMapEntryKind::KERNEL, 0x10_0000, 0x4000),
+            entry(MemoryMapEntryKind::KERNEL, 0x10_4000, 0x2000),
+            entry(MemoryMapEntryKind::BOOTLOADER, 0x20_0000, 0x1000),
+        ];
+        // SAFETY: every field of `Framebuffer` is an integer or a raw pointer, for which all
+        // zeroes is a valid value.
+        let mut framebuffer: Framebuffer = unsafe { core::mem::zeroed() };
+        framebuffer.address = (DIRECT_MAP + 0xfd00_0000) as *mut u8;
+        framebuffer.height = 768;
+        framebuffer.pitch = 4096;
+
+        let mut response = response();
+        response.direct_map = DIRECT_MAP;
+        response.kernel_virtual_address = KERNEL as *const _;
+        response.framebuffers = &mut framebuffer;
+        response.framebuffer_count = 1;
+        set_memory_map(&mut response, &mut entries);
+
+        // SAFETY: the memory map and framebuffers point to locals that outlive `response`.
+        let plan = unsafe { response.early_map_plan() }.collect::<Vec<_>>();
+        let kernel = MemoryMapEntryKind::KERNEL.default_permissions();
+        assert_eq!(
+            plan,
+            [
+                MapRequest {
+                    phys: 0x10_0000,
+                    virt: KERNEL as u64,
+                    size: 0x4000,
+                    flags: kernel,
+                },
+                MapRequest {
+                    phys: 0x10_4000,
+                    virt: KERNEL as u64 + 0x4000,
+                    size: 0x2000,
+                    flags: kernel,
+                },
+                MapRequest {
+                    phys: 0x20_0000,
+                    virt: DIRECT_MAP as u64 + 0x20_0000,
+                    size: 0x1000,
+                    flags: MapFlags::READ | MapFlags::WRITE,
+                },
+                MapRequest {
+                    phys: 0xfd00_0000,
+                    virt: DIRECT_MAP as u64 + 0xfd00_0000,
+                    size: 768 * 4096,
+                    flags: MapFlags::READ | MapFlags::WRITE | MapFlags::WRITE_COMBINING,
+                },
+            ]
+        );
+        assert!(kernel.contains(MapFlags::EXECUTE) && !kernel.contains(MapFlags::WRITE));
+    }
+
+    #[test]
+    fn default_permissions_per_kind() {
+        let read_write = MapFlags::READ | MapFlags::WRITE;
+        let cases = [
+            (MemoryMapEntryKind::USABLE, read_write),
+            (MemoryMapEntryKind::BOOTLOADER, read_write),
+            (MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE, read_write),
+            (MemoryMapEntryKind::ACPI_RECLAIMABLE, MapFlags::READ),
+            (MemoryMapEntryKind::MODULE, MapFlags::READ),
+            (
+                MemoryMapEntryKind::KERNEL,
+                MapFlags::READ | MapFlags::EXECUTE,
+            ),
+            (MemoryMapEntryKind::RESERVED, MapFlags::NONE),
+            (MemoryMapEntryKind::UNUSABLE, MapFlags::NONE),
+            (MemoryMapEntryKind::UNACCEPTED, MapFlags::NONE),
+        ];
+        for (kind, flags) in cases {
+            assert_eq!(kind.default_permissions(), flags, "{}", kind.name());
+        }
+
+        // SAFETY: every field of `Framebuffer` is an integer or a raw pointer, for which all
+        // zeroes is a valid value.
+        let framebuffer: Framebuffer = unsafe { core::mem::zeroed() };
+        assert_eq!(
+            framebuffer.default_permissions(),
+            read_write | MapFlags::WRITE_COMBINING
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Helpers for locating the [`BootloaderRequest`] embedded in a kernel image.
+
+use crate::{is_supported_version, BootloaderRequest, API_VERSION, SIGNATURE};
+
+/// The name of the section into which [`capora_boot_request!`](crate::capora_boot_request)
+/// places the [`BootloaderRequest`].
+///
+/// The kernel's linker script must place this section in a segment of type
+/// [`BOOTLOADER_REQUEST_ELF_SEGMENT`], which is what the bootloader scans for.
+///
+/// [`BOOTLOADER_REQUEST_ELF_SEGMENT`]: crate::BOOTLOADER_REQUEST_ELF_SEGMENT
+pub const BOOTLOADER_REQUEST_SECTION: &str = ".capora_boot_request";
+
+/// Declares a `static REQUEST: BootloaderRequest` in the [`BOOTLOADER_REQUEST_SECTION`],
+/// asking for the current [`API_VERSION`] and, optionally, the given
+/// [`BootloaderRequest::requested_features`].
+///
+/// # Examples
+/// ```
+/// boot_api::capora_boot_request!(boot_api::CAP_FRAMEBUFFER);
+///
+/// assert_eq!(REQUEST.api_version, boot_api::API_VERSION);
+/// assert_eq!(REQUEST.requested_features(), boot_api::CAP_FRAMEBUFFER);
+/// assert_eq!(REQUEST.validate(), Ok(()));
+/// ```
+///
+/// [`API_VERSION`]: crate::API_VERSION
+#[macro_export]
+macro_rules! capora_boot_request {
+    () => {
+        $crate::capora_boot_request!(0);
+    };
+    ($features:expr) => {
+        #[used]
+        #[link_section = ".capora_boot_request"]
+        static REQUEST: $crate::BootloaderRequest =
+            $crate::BootloaderRequest::new().with_features($features);
+    };
+}
+
+/// Various errors that can occur when interpreting bytes as a [`BootloaderRequest`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RequestError {
+    /// The bytes are too short to contain a [`BootloaderRequest`].
+    TooShort,
+    /// The bytes are not properly aligned for a [`BootloaderRequest`].
+    Misaligned,
+    /// The bytes do not start with [`SIGNATURE`].
+    InvalidSignature,
+    /// The request asks for an API version that is not supported.
+    UnsupportedVersion,
+}
+
+impl core::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooShort => f.write_str("bootloader request is truncated"),
+            Self::Misaligned => f.write_str("bootloader request is misaligned"),
+            Self::InvalidSignature => f.write_str("bootloader request has an invalid signature"),
+            Self::UnsupportedVersion => {
+                f.write_str("bootloader request has an unsupported api version")
+            }
+        }
+    }
+}
+
+/// The API version in which [`BootloaderRequest::requested_features`] was introduced.
+const REQUESTED_FEATURES_VERSION: u64 = 11;
+
+/// The size, in bytes, of a [`BootloaderRequest`] from before
+/// [`BootloaderRequest::requested_features`] was introduced.
+const LEGACY_REQUEST_SIZE: usize = core::mem::offset_of!(BootloaderRequest, requested_features);
+
+/// Interprets the start of `bytes`, usually the contents of the
+/// [`BOOTLOADER_REQUEST_ELF_SEGMENT`], as a [`BootloaderRequest`].
+///
+/// Requests from before API version 11 end before [`BootloaderRequest::requested_features`]
+/// and so may be too short to borrow, use [`copy_request_from_bytes`] to read those.
+///
+/// # Errors
+/// - [`RequestError::TooShort`]: `bytes` is shorter than a [`BootloaderRequest`].
+/// - [`RequestError::Misaligned`]: `bytes` is not aligned to a [`BootloaderRequest`].
+/// - [`RequestError::InvalidSignature`]: the request does not start with [`SIGNATURE`].
+///
+/// [`BOOTLOADER_REQUEST_ELF_SEGMENT`]: crate::BOOTLOADER_REQUEST_ELF_SEGMENT
+pub fn request_from_bytes(bytes: &[u8]) -> Result<&BootloaderRequest, RequestError> {
+    if bytes.len() < core::mem::size_of::<BootloaderRequest>() {
+        return Err(RequestError::TooShort);
+    }
+    if bytes
+        .as_ptr()
+        .align_offset(core::mem::align_of::<BootloaderRequest>())
+        != 0
+    {
+        return Err(RequestError::Misaligned);
+    }
+
+    // SAFETY: `bytes` is aligned for and at least as large as a `BootloaderRequest`, which
+    // consists only of integers and so is valid for any bit pattern.
+    let request = unsafe { &*bytes.as_ptr().cast::<BootloaderRequest>() };
+    if request.signature != SIGNATURE {
+        return Err(RequestError::InvalidSignature);
+    }
+
+    Ok(request)
+}
+
+/// Reads a [`BootloaderRequest`] from the start of `bytes`, which need not be aligned.
+///
+/// Requests from before API version 11 end before [`BootloaderRequest::requested_features`],
+/// so only their first [`BootloaderRequest::api_version`] is read to determine how many bytes
+/// are required. Such requests are returned with no requested features.
+///
+/// # Errors
+/// - [`RequestError::TooShort`]: `bytes` is shorter than a [`BootloaderRequest`] of the
+///   requested API version.
+/// - [`RequestError::InvalidSignature`]: the request does not start with [`SIGNATURE`].
+pub fn copy_request_from_bytes(bytes: &[u8]) -> Result<BootloaderRequest, RequestError> {
+    if bytes.len() < LEGACY_REQUEST_SIZE {
+        return Err(RequestError::TooShort);
+    }
+
+    let read = |offset: usize| {
+        let mut word = [0; 8];
+        word.copy_from_slice(&bytes[offset..offset + 8]);
+        u64::from_ne_bytes(word)
+    };
+
+    let signature = [read(0), read(8), read(16)];
+    if signature != SIGNATURE {
+        return Err(RequestError::InvalidSignature);
+    }
+
+    let api_version = read(core::mem::offset_of!(BootloaderRequest, api_version));
+    let requested_features = if api_version < REQUESTED_FEATURES_VERSION {
+        0
+    } else if bytes.len() < core::mem::size_of::<BootloaderRequest>() {
+        return Err(RequestError::TooShort);
+    } else {
+        read(LEGACY_REQUEST_SIZE)
+    };
+
+    Ok(BootloaderRequest {
+        signature,
+        api_version,
+        requested_features,
+    })
+}
+
+impl BootloaderRequest {
+    /// Creates a new [`BootloaderRequest`] asking for the current [`API_VERSION`] and no
+    /// optional features.
+    pub const fn new() -> Self {
+        Self {
+            signature: SIGNATURE,
+            api_version: API_VERSION,
+            requested_features: 0,
+        }
+    }
+
+    /// Returns this [`BootloaderRequest`] additionally asking for the `CAP_*` flags in
+    /// `features`.
+    pub const fn with_features(self, features: u64) -> Self {
+        Self {
+            requested_features: self.requested_features | features,
+            ..self
+        }
+    }
+
+    /// Returns the `CAP_*` flags the kernel asks the bootloader to provide, or 0 if the
+    /// request predates [`BootloaderRequest::requested_features`].
+    pub const fn requested_features(&self) -> u64 {
+        if self.api_version < REQUESTED_FEATURES_VERSION {
+            return 0;
+        }
+
+        self.requested_features
+    }
+
+    /// Validates that this [`BootloaderRequest`] has the correct signature and asks for a
+    /// supported API version.
+    ///
+    /// # Errors
+    /// - [`RequestError::InvalidSignature`]: the request does not start with [`SIGNATURE`].
+    /// - [`RequestError::UnsupportedVersion`]: [`is_supported_version`] rejects the requested
+    ///   API version.
+    pub fn validate(&self) -> Result<(), RequestError> {
+        if self.signature != SIGNATURE {
+            return Err(RequestError::InvalidSignature);
+        }
+        if !is_supported_version(self.api_version) {
+            return Err(RequestError::UnsupportedVersion);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for BootloaderRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BOOTLOADER_REQUEST_SIZE;
+
+    /// A buffer aligned for a [`BootloaderRequest`], with room to misalign it.
+    #[repr(C, align(8))]
+    struct Buffer([u8; BOOTLOADER_REQUEST_SIZE + 8]);
+
+    impl Buffer {
+        /// Returns a [`Buffer`] holding the bytes of `request` at `offset`.
+        fn new(request: &BootloaderRequest, offset: usize) -> Self {
+            let words = request
+                .signature
+                .iter()
+                .chain([&request.api_version, &request.requested_features]);
+
+            let mut buffer = Self([0; BOOTLOADER_REQUEST_SIZE + 8]);
+            for (chunk, word) in buffer.0[offset..].chunks_mut(8).zip(words) {
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            buffer
+        }
+    }
+
+    #[test]
+    fn too_short() {
+        let buffer = Buffer::new(&BootloaderRequest::new(), 0);
+        assert_eq!(
+            request_from_bytes(&buffer.0[..BOOTLOADER_REQUEST_SIZE - 1]),
+            Err(RequestError::TooShort)
+        );
+        assert_eq!(request_from_bytes(&[]), Err(RequestError::TooShort));
+
+        assert_eq!(
+            copy_request_from_bytes(&buffer.0[..BOOTLOADER_REQUEST_SIZE - 1]),
+            Err(RequestError::TooShort)
+        );
+        assert_eq!(
+            copy_request_from_bytes(&buffer.0[..LEGACY_REQUEST_SIZE - 1]),
+            Err(RequestError::TooShort)
+        );
+    }
+
+    #[test]
+    fn misaligned() {
+        let request = BootloaderRequest::new();
+        let buffer = Buffer::new(&request, 1);
+        assert_eq!(
+            request_from_bytes(&buffer.0[1..]),
+            Err(RequestError::Misaligned)
+        );
+        assert_eq!(copy_request_from_bytes(&buffer.0[1..]), Ok(request));
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let mut request = BootloaderRequest::new();
+        request.signature[1] ^= 1;
+        let buffer = Buffer::new(&request, 0);
+        assert_eq!(
+            request_from_bytes(&buffer.0),
+            Err(RequestError::InvalidSignature)
+        );
+        assert_eq!(
+            copy_request_from_bytes(&buffer.0),
+            Err(RequestError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn valid() {
+        let request = BootloaderRequest::new().with_features(crate::CAP_FRAMEBUFFER);
+        let buffer = Buffer::new(&request, 0);
+
+        let borrowed = request_from_bytes(&buffer.0).unwrap();
+        assert_eq!(*borrowed, request);
+        assert!(core::ptr::eq(
+            (borrowed as *const BootloaderRequest).cast::<u8>(),
+            buffer.0.as_ptr()
+        ));
+        assert_eq!(copy_request_from_bytes(&buffer.0), Ok(request));
+        assert_eq!(request.requested_features(), crate::CAP_FRAMEBUFFER);
+    }
+
+    #[test]
+    fn legacy_request_has_no_features() {
+        let mut request = BootloaderRequest::new().with_features(crate::CAP_FRAMEBUFFER);
+        request.api_version = 10;
+        let buffer = Buffer::new(&request, 0);
+
+        let expected = BootloaderRequest {
+            requested_features: 0,
+            ..request
+        };
+        assert_eq!(
+            copy_request_from_bytes(&buffer.0[..LEGACY_REQUEST_SIZE]),
+            Ok(expected)
+        );
+        assert_eq!(copy_request_from_bytes(&buffer.0), Ok(expected));
+        assert_eq!(
+            request_from_bytes(&buffer.0).map(BootloaderRequest::requested_features),
+            Ok(0)
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Helpers for locating the SMBIOS structures referenced by a [`BootloaderResponse`].
+
+use crate::BootloaderResponse;
+
+/// The anchor string at the start of an SMBIOS 3.0 entry point.
+const SMBIOS_3_ANCHOR: [u8; 5] = *b"_SM3_";
+
+/// The offset of the structure table maximum size field in an SMBIOS 3.0 entry point.
+const STRUCTURE_TABLE_MAX_SIZE_OFFSET: usize = 0x0C;
+
+/// The offset of the structure table address field in an SMBIOS 3.0 entry point.
+const STRUCTURE_TABLE_ADDRESS_OFFSET: usize = 0x10;
+
+impl BootloaderResponse {
+    /// Returns a pointer to the SMBIOS structure table and its maximum size, in bytes, as
+    /// described by the 64-bit SMBIOS entry point.
+    ///
+    /// The structure table address is translated through [`BootloaderResponse::direct_map`].
+    /// Returns [`None`] if [`BootloaderResponse::sm_bios_entry_64`] is NULL or does not point
+    /// to an SMBIOS 3.0 entry point.
+    ///
+    /// # Safety
+    /// If [`BootloaderResponse::sm_bios_entry_64`] is not NULL, it must point to at least 24
+    /// readable bytes.
+    pub unsafe fn smbios_structure_table(&self) -> Option<(*const u8, usize)> {
+        let entry = self.sm_bios_entry_64.cast::<u8>();
+        if entry.is_null() {
+            return None;
+        }
+
+        // SAFETY: the caller guarantees that the entry point is readable.
+        let anchor = unsafe { entry.cast::<[u8; 5]>().read_unaligned() };
+        if anchor != SMBIOS_3_ANCHOR {
+            return None;
+        }
+
+        // SAFETY: the caller guarantees that the entry point is readable.
+        let max_size = unsafe {
+            entry
+                .add(STRUCTURE_TABLE_MAX_SIZE_OFFSET)
+                .cast::<u32>()
+                .read_unaligned()
+        };
+        // SAFETY: the caller guarantees that the entry point is readable.
+        let address = unsafe {
+            entry
+                .add(STRUCTURE_TABLE_ADDRESS_OFFSET)
+                .cast::<u64>()
+                .read_unaligned()
+        };
+
+        let table = self.direct_map().phys_to_virt(address) as *const u8;
+        Some((table, max_size as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::response;
+
+    /// Returns an SMBIOS 3.0 entry point describing a structure table of `max_size` bytes at
+    /// `address`.
+    fn entry_point(max_size: u32, address: u64) -> [u8; 24] {
+        let mut entry = [0; 24];
+        entry[..5].copy_from_slice(b"_SM3_");
+        entry[0x06] = 0x18;
+        entry[0x07] = 3;
+        entry[0x0A] = 1;
+        entry[0x0C..0x10].copy_from_slice(&max_size.to_le_bytes());
+        entry[0x10..0x18].copy_from_slice(&address.to_le_bytes());
+        entry
+    }
+
+    #[test]
+    fn structure_table() {
+        let entry = entry_point(0x1234, 0x000f_0000);
+        let mut response = response();
+        response.direct_map = 0x8000_0000;
+        response.sm_bios_entry_64 = entry.as_ptr().cast();
+
+        // SAFETY: the entry point points to `entry`, which outlives `response`.
+        let table = unsafe { response.smbios_structure_table() };
+        assert_eq!(table, Some((0x800f_0000 as *const u8, 0x1234)));
+
+        let mut entry = entry;
+        entry[0] = b'X';
+        response.sm_bios_entry_64 = entry.as_ptr().cast();
+        // SAFETY: the entry point points to `entry`, which outlives `response`.
+        assert_eq!(unsafe { response.smbios_structure_table() }, None);
+
+        response.sm_bios_entry_64 = core::ptr::null();
+        // SAFETY: the entry point is NULL.
+        assert_eq!(unsafe { response.smbios_structure_table() }, None);
+    }
+}
@@ -0,0 +1,323 @@
+//! A pointer-free summary of a [`BootloaderResponse`].
+
+use crate::{fields::FieldMask, BootloaderResponse};
+
+/// A plain summary of a [`BootloaderResponse`] that contains no pointers.
+///
+/// Since it holds no pointers, a [`ResponseSummary`] is [`Send`], [`Sync`], and [`Copy`], and
+/// can be stashed anywhere, such as a ring buffer or a debug channel.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ResponseSummary {
+    /// The value of [`BootloaderResponse::api_version`].
+    pub api_version: u64,
+    /// The value of [`BootloaderResponse::memory_map_entry_count`].
+    pub memory_map_entry_count: usize,
+    /// The value of [`BootloaderResponse::module_entry_count`].
+    pub module_count: usize,
+    /// Whether [`BootloaderResponse::rsdp_table_ptr`] is non-NULL.
+    pub has_rsdp: bool,
+    /// Whether [`BootloaderResponse::sm_bios_entry_32`] is non-NULL.
+    pub has_sm_bios_32: bool,
+    /// Whether [`BootloaderResponse::sm_bios_entry_64`] is non-NULL.
+    pub has_sm_bios_64: bool,
+    /// Whether [`BootloaderResponse::uefi_system_table_ptr`] is non-NULL.
+    pub has_uefi_system_table: bool,
+    /// Whether [`BootloaderResponse::uefi_memory_map`] is non-NULL.
+    pub has_uefi_memory_map: bool,
+    /// Whether [`BootloaderResponse::framebuffer_count`] is non-zero.
+    pub has_framebuffer: bool,
+}
+
+impl BootloaderResponse {
+    /// Returns a [`ResponseSummary`] of this [`BootloaderResponse`].
+    pub fn summary(&self) -> ResponseSummary {
+        ResponseSummary {
+            api_version: self.api_version,
+            memory_map_entry_count: self.memory_map_entry_count,
+            module_count: self.module_entry_count,
+            has_rsdp: !self.rsdp_table_ptr.is_null(),
+            has_sm_bios_32: !self.sm_bios_entry_32.is_null(),
+            has_sm_bios_64: !self.sm_bios_entry_64.is_null(),
+            has_uefi_system_table: !self.uefi_system_table_ptr.is_null(),
+            has_uefi_memory_map: !self.uefi_memory_map.is_null(),
+            has_framebuffer: self.framebuffer_count != 0,
+        }
+    }
+}
+
+/// Returns `true` if `a` and `b` describe the same machine.
+///
+/// Two responses are equivalent if their memory maps contain the same entries, regardless of
+/// order, and they agree on the presence of the RSDP and SMBIOS entry points. Pointer values,
+/// which legitimately differ between handoffs, are ignored.
+///
+/// # Safety
+/// The same requirements as [`BootloaderResponse::memory_map()`] apply to both `a` and `b`.
+#[cfg(feature = "alloc")]
+pub unsafe fn responses_equivalent(a: &BootloaderResponse, b: &BootloaderResponse) -> bool {
+    let (a_summary, b_summary) = (a.summary(), b.summary());
+    if a_summary.has_rsdp != b_summary.has_rsdp
+        || a_summary.has_sm_bios_32 != b_summary.has_sm_bios_32
+        || a_summary.has_sm_bios_64 != b_summary.has_sm_bios_64
+    {
+        return false;
+    }
+
+    // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+    let (mut a_map, mut b_map) = unsafe { (a.memory_map().to_vec(), b.memory_map().to_vec()) };
+    a_map.sort_unstable();
+    b_map.sort_unstable();
+    a_map == b_map
+}
+
+/// The size, in bytes, of the serialized form of a [`ResponseScalars`].
+pub const RESPONSE_SCALARS_SIZE: usize = 21 * 8;
+
+/// The non-pointer fields of a [`BootloaderResponse`], widened to 64 bits.
+///
+/// Unlike a [`BootloaderResponse`], a [`ResponseScalars`] can be serialized and read back by
+/// a host of any endianness or pointer width. Fields that
+/// [`BootloaderResponse::api_version`] does not make available, see [`FieldMask`], hold 0.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ResponseScalars {
+    /// The value of [`BootloaderResponse::api_version`].
+    pub api_version: u64,
+    /// The value of [`BootloaderResponse::bootloader_name_length`].
+    pub bootloader_name_length: u64,
+    /// The value of [`BootloaderResponse::bootloader_version_length`].
+    pub bootloader_version_length: u64,
+    /// The value of [`BootloaderResponse::direct_map`].
+    pub direct_map: u64,
+    /// The value of [`BootloaderResponse::memory_map_entry_count`].
+    pub memory_map_entry_count: u64,
+    /// The value of [`BootloaderResponse::uefi_memory_map_size`].
+    pub uefi_memory_map_size: u64,
+    /// The value of [`BootloaderResponse::uefi_memory_map_descriptor_size`].
+    pub uefi_memory_map_descriptor_size: u64,
+    /// The value of [`BootloaderResponse::uefi_memory_map_descriptor_version`].
+    pub uefi_memory_map_descriptor_version: u64,
+    /// The value of [`BootloaderResponse::module_entry_count`].
+    pub module_entry_count: u64,
+    /// The value of [`BootloaderResponse::protocol_revision_length`].
+    pub protocol_revision_length: u64,
+    /// The value of [`BootloaderResponse::paging_levels`].
+    pub paging_levels: u64,
+    /// The value of [`BootloaderResponse::capabilities`].
+    pub capabilities: u64,
+    /// The value of [`BootloaderResponse::page_table_root`].
+    pub page_table_root: u64,
+    /// The value of [`BootloaderResponse::framebuffer_count`].
+    pub framebuffer_count: u64,
+    /// The value of [`BootloaderResponse::uefi_runtime_available`].
+    pub uefi_runtime_available: u64,
+    /// The value of [`BootloaderResponse::kaslr_slide`].
+    pub kaslr_slide: i64,
+    /// The value of [`BootloaderResponse::rsdp_physical_address`].
+    pub rsdp_physical_address: u64,
+    /// The value of [`BootloaderResponse::firmware_vendor_length`].
+    pub firmware_vendor_length: u64,
+    /// The value of [`BootloaderResponse::kernel_physical_address`].
+    pub kernel_physical_address: u64,
+    /// The value of [`BootloaderResponse::kernel_size`].
+    pub kernel_size: u64,
+    /// The value of [`BootloaderResponse::memory_map_checksum`].
+    pub memory_map_checksum: u64,
+}
+
+impl ResponseScalars {
+    /// Returns the fields of this [`ResponseScalars`] in serialization order.
+    const fn fields(&self) -> [u64; RESPONSE_SCALARS_SIZE / 8] {
+        [
+            self.api_version,
+            self.bootloader_name_length,
+            self.bootloader_version_length,
+            self.direct_map,
+            self.memory_map_entry_count,
+            self.uefi_memory_map_size,
+            self.uefi_memory_map_descriptor_size,
+            self.uefi_memory_map_descriptor_version,
+            self.module_entry_count,
+            self.protocol_revision_length,
+            self.paging_levels,
+            self.capabilities,
+            self.page_table_root,
+            self.framebuffer_count,
+            self.uefi_runtime_available,
+            self.kaslr_slide as u64,
+            self.rsdp_physical_address,
+            self.firmware_vendor_length,
+            self.kernel_physical_address,
+            self.kernel_size,
+            self.memory_map_checksum,
+        ]
+    }
+
+    /// Serializes this [`ResponseScalars`] as consecutive little-endian 64-bit fields, in
+    /// declaration order.
+    pub fn to_le_bytes(&self) -> [u8; RESPONSE_SCALARS_SIZE] {
+        let mut bytes = [0; RESPONSE_SCALARS_SIZE];
+        for (chunk, field) in bytes.chunks_exact_mut(8).zip(self.fields()) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserializes a [`ResponseScalars`] produced by [`ResponseScalars::to_le_bytes`].
+    pub fn from_le_bytes(bytes: &[u8; RESPONSE_SCALARS_SIZE]) -> Self {
+        let mut fields = bytes.chunks_exact(8).map(|chunk| {
+            let mut field = [0; 8];
+            field.copy_from_slice(chunk);
+            u64::from_le_bytes(field)
+        });
+        let mut next = || fields.next().unwrap_or(0);
+
+        Self {
+            api_version: next(),
+            bootloader_name_length: next(),
+            bootloader_version_length: next(),
+            direct_map: next(),
+            memory_map_entry_count: next(),
+            uefi_memory_map_size: next(),
+            uefi_memory_map_descriptor_size: next(),
+            uefi_memory_map_descriptor_version: next(),
+            module_entry_count: next(),
+            protocol_revision_length: next(),
+            paging_levels: next(),
+            capabilities: next(),
+            page_table_root: next(),
+            framebuffer_count: next(),
+            uefi_runtime_available: next(),
+            kaslr_slide: next() as i64,
+            rsdp_physical_address: next(),
+            firmware_vendor_length: next(),
+            kernel_physical_address: next(),
+            kernel_size: next(),
+            memory_map_checksum: next(),
+        }
+    }
+}
+
+impl BootloaderResponse {
+    /// Returns the [`ResponseScalars`] of this [`BootloaderResponse`].
+    pub fn scalars(&self) -> ResponseScalars {
+        ResponseScalars {
+            api_version: self.api_version,
+            bootloader_name_length: self.bootloader_name_length as u64,
+            bootloader_version_length: self.bootloader_version_length as u64,
+            direct_map: self.direct_map as u64,
+            memory_map_entry_count: self.memory_map_entry_count as u64,
+            uefi_memory_map_size: self.uefi_memory_map_size as u64,
+            uefi_memory_map_descriptor_size: self.uefi_memory_map_descriptor_size as u64,
+            uefi_memory_map_descriptor_version: self.uefi_memory_map_descriptor_version,
+            module_entry_count: self.module_entry_count as u64,
+            protocol_revision_length: self.protocol_revision_length as u64,
+            paging_levels: u64::from(self.paging_levels()),
+            capabilities: self.capabilities,
+            page_table_root: self.page_table_root(),
+            framebuffer_count: self.framebuffer_count as u64,
+            uefi_runtime_available: u64::from(self.uefi_runtime_available()),
+            kaslr_slide: self.kaslr_slide(),
+            rsdp_physical_address: self
+                .gated(FieldMask::RSDP_PHYSICAL_ADDRESS, self.rsdp_physical_address),
+            firmware_vendor_length: self.gated(
+                FieldMask::FIRMWARE_VENDOR,
+                self.firmware_vendor_length as u64,
+            ),
+            kernel_physical_address: self
+                .gated(FieldMask::KERNEL_PHYSICAL, self.kernel_physical_address),
+            kernel_size: self.gated(FieldMask::KERNEL_PHYSICAL, self.kernel_size),
+            memory_map_checksum: self.memory_map_checksum().unwrap_or(0),
+        }
+    }
+
+    /// Serializes the [`ResponseScalars`] of this [`BootloaderResponse`], see
+    /// [`ResponseScalars::to_le_bytes`].
+    pub fn response_scalars_le(&self) -> [u8; RESPONSE_SCALARS_SIZE] {
+        self.scalars().to_le_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::response, API_VERSION};
+    #[cfg(feature = "alloc")]
+    use crate::{
+        test_util::{entry, set_memory_map},
+        MemoryMapEntryKind,
+    };
+
+    #[test]
+    fn summary_reports_presence() {
+        let rsdp = [0u8; 36];
+        let mut response = response();
+        response.rsdp_table_ptr = rsdp.as_ptr().cast();
+        response.framebuffer_count = 1;
+        response.memory_map_entry_count = 3;
+        response.module_entry_count = 2;
+
+        assert_eq!(
+            response.summary(),
+            ResponseSummary {
+                api_version: API_VERSION,
+                memory_map_entry_count: 3,
+                module_count: 2,
+                has_rsdp: true,
+                has_framebuffer: true,
+                ..ResponseSummary::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn responses_differing_in_pointers_are_equivalent() {
+        let rsdp = [[0u8; 36]; 2];
+        let mut a_entries = [
+            entry(MemoryMapEntryKind::USABLE, 0, 0x1000),
+            entry(MemoryMapEntryKind::KERNEL, 0x1000, 0x1000),
+        ];
+        let mut b_entries = [a_entries[1], a_entries[0]];
+
+        let (mut a, mut b) = (response(), response());
+        a.rsdp_table_ptr = rsdp[0].as_ptr().cast();
+        b.rsdp_table_ptr = rsdp[1].as_ptr().cast();
+        set_memory_map(&mut a, &mut a_entries);
+        set_memory_map(&mut b, &mut b_entries);
+
+        // SAFETY: the memory maps point to locals that outlive both responses.
+        assert!(unsafe { responses_equivalent(&a, &b) });
+
+        b.rsdp_table_ptr = core::ptr::null();
+        // SAFETY: the memory maps point to locals that outlive both responses.
+        assert!(!unsafe { responses_equivalent(&a, &b) });
+    }
+
+    #[test]
+    fn scalars_round_trip_little_endian() {
+        let mut response = response();
+        response.bootloader_name_length = 6;
+        response.direct_map = 0xffff_8000_0000_0000;
+        response.memory_map_entry_count = 12;
+        response.module_entry_count = 2;
+        response.paging_levels = 4;
+        response.capabilities = crate::CAP_FRAMEBUFFER;
+        response.page_table_root = 0x5000;
+        response.framebuffer_count = 1;
+        response.uefi_runtime_available = 1;
+        response.kaslr_slide = -0x20_0000;
+        response.rsdp_physical_address = 0xe_0000;
+        response.firmware_vendor_length = 6;
+        response.kernel_physical_address = 0x10_0000;
+        response.kernel_size = 0x4000;
+
+        let bytes = response.response_scalars_le();
+        assert_eq!(bytes[..8], API_VERSION.to_le_bytes());
+        assert_eq!(bytes[24..32], 0xffff_8000_0000_0000_u64.to_le_bytes());
+
+        let scalars = ResponseScalars::from_le_bytes(&bytes);
+        assert_eq!(scalars, response.scalars());
+        assert_eq!(scalars.kaslr_slide, -0x20_0000);
+        assert_eq!(scalars.to_le_bytes(), bytes);
+    }
+}
@@ -0,0 +1,64 @@
+//! Fixtures shared by the unit tests of this crate.
+
+use crate::{
+    memory_map, BootloaderResponse, Framebuffer, MemoryMapEntry, MemoryMapEntryKind, ModuleEntry,
+    API_VERSION, RESPONSE_SIGNATURE,
+};
+
+/// Returns a [`BootloaderResponse`] for the current [`API_VERSION`] with every other field
+/// zeroed.
+pub(crate) fn response() -> BootloaderResponse {
+    // SAFETY: every field of `BootloaderResponse` is an integer or a raw pointer, for which
+    // all zeroes is a valid value.
+    let mut response: BootloaderResponse = unsafe { core::mem::zeroed() };
+    response.signature = RESPONSE_SIGNATURE;
+    response.api_version = API_VERSION;
+    response.memory_map_checksum = memory_map::checksum(&[]);
+    response
+}
+
+/// Returns a [`MemoryMapEntry`] of `kind` covering `size` bytes starting at `base`.
+pub(crate) const fn entry(kind: MemoryMapEntryKind, base: u64, size: u64) -> MemoryMapEntry {
+    MemoryMapEntry { kind, base, size }
+}
+
+/// Points the memory map of `response` at `entries` and updates its checksum.
+pub(crate) fn set_memory_map(response: &mut BootloaderResponse, entries: &mut [MemoryMapEntry]) {
+    response.memory_map_checksum = memory_map::checksum(entries);
+    response.memory_map_entries = entries.as_mut_ptr();
+    response.memory_map_entry_count = entries.len();
+}
+
+/// Returns a [`ModuleEntry`] named `name` covering `size` bytes starting at `address`.
+pub(crate) fn module(name: &'static str, address: *const u8, size: usize) -> ModuleEntry {
+    ModuleEntry {
+        name: name.as_ptr(),
+        name_length: name.len(),
+        address,
+        size,
+        flags: 0,
+    }
+}
+
+/// Points the modules of `response` at `modules`.
+pub(crate) fn set_modules(response: &mut BootloaderResponse, modules: &mut [ModuleEntry]) {
+    response.module_entries = modules.as_mut_ptr();
+    response.module_entry_count = modules.len();
+}
+
+/// Returns a [`Framebuffer`] of `width` by `height` 32-bit pixels starting at `address`.
+pub(crate) fn framebuffer(address: *mut u8, width: u64, height: u64) -> Framebuffer {
+    Framebuffer {
+        address,
+        width,
+        height,
+        pitch: width * 4,
+        bits_per_pixel: 32,
+        red_mask_size: 8,
+        red_mask_shift: 16,
+        green_mask_size: 8,
+        green_mask_shift: 8,
+        blue_mask_size: 8,
+        blue_mask_shift: 0,
+    }
+}
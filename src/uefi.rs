@@ -0,0 +1,453 @@
+//! Interpretation of the UEFI memory map passed through the [`BootloaderResponse`].
+
+use crate::{BootloaderResponse, MemoryMapEntry, MemoryMapEntryKind};
+
+/// The version of the UEFI memory map descriptors provided by the firmware.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UefiDescriptorVersion(pub u64);
+
+impl UefiDescriptorVersion {
+    /// The descriptor layout described by [`UefiMemoryDescriptor`].
+    pub const V1: Self = Self(1);
+
+    /// Returns `true` if this crate knows how to interpret descriptors of this version.
+    ///
+    /// Only [`UefiDescriptorVersion::V1`] is currently supported.
+    pub const fn is_supported(self) -> bool {
+        self.0 == Self::V1.0
+    }
+}
+
+/// A single descriptor of the UEFI memory map, as laid out in version 1 of the descriptor
+/// format.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UefiMemoryDescriptor {
+    /// The UEFI memory type of the region.
+    pub kind: u32,
+    /// The physical address of the first byte of the region.
+    pub physical_start: u64,
+    /// The virtual address of the first byte of the region.
+    pub virtual_start: u64,
+    /// The number of 4096 byte pages in the region.
+    pub number_of_pages: u64,
+    /// The attributes of the region.
+    pub attribute: u64,
+}
+
+impl UefiMemoryDescriptor {
+    /// Memory that is not usable.
+    pub const RESERVED_MEMORY_TYPE: u32 = 0;
+    /// The code of a loaded UEFI application.
+    pub const LOADER_CODE: u32 = 1;
+    /// The data of a loaded UEFI application.
+    pub const LOADER_DATA: u32 = 2;
+    /// The code of UEFI boot services drivers.
+    pub const BOOT_SERVICES_CODE: u32 = 3;
+    /// The data of UEFI boot services drivers.
+    pub const BOOT_SERVICES_DATA: u32 = 4;
+    /// The code of UEFI runtime services drivers.
+    pub const RUNTIME_SERVICES_CODE: u32 = 5;
+    /// The data of UEFI runtime services drivers.
+    pub const RUNTIME_SERVICES_DATA: u32 = 6;
+    /// Free memory.
+    pub const CONVENTIONAL_MEMORY: u32 = 7;
+    /// Memory in which errors have been detected.
+    pub const UNUSABLE_MEMORY: u32 = 8;
+    /// Memory that holds ACPI tables.
+    pub const ACPI_RECLAIM_MEMORY: u32 = 9;
+    /// Memory reserved for use by the firmware.
+    pub const ACPI_MEMORY_NVS: u32 = 10;
+    /// Memory used by the firmware for memory-mapped IO.
+    pub const MEMORY_MAPPED_IO: u32 = 11;
+    /// Memory used by the firmware to translate memory cycles to IO cycles.
+    pub const MEMORY_MAPPED_IO_PORT_SPACE: u32 = 12;
+    /// Memory reserved by the firmware for processor code.
+    pub const PAL_CODE: u32 = 13;
+    /// Free memory that is persistent.
+    pub const PERSISTENT_MEMORY: u32 = 14;
+    /// Memory that must be accepted before use.
+    pub const UNACCEPTED_MEMORY_TYPE: u32 = 15;
+
+    /// The attribute indicating that the region must be mapped for UEFI runtime services.
+    pub const MEMORY_RUNTIME: u64 = 1 << 63;
+
+    /// Returns the physical address one past the end of the region, saturating at the top of
+    /// the physical address space.
+    pub const fn physical_end(&self) -> u64 {
+        self.physical_start
+            .saturating_add(self.number_of_pages.saturating_mul(4096))
+    }
+
+    /// Returns the [`MemoryMapEntryKind`] that best describes the region once boot services
+    /// have exited.
+    ///
+    /// | UEFI memory type                                    | [`MemoryMapEntryKind`]                  |
+    /// |-----------------------------------------------------|-----------------------------------------|
+    /// | `CONVENTIONAL_MEMORY`, `BOOT_SERVICES_{CODE,DATA}`  | [`MemoryMapEntryKind::USABLE`]          |
+    /// | `LOADER_{CODE,DATA}`                                | [`MemoryMapEntryKind::BOOTLOADER`]      |
+    /// | `ACPI_RECLAIM_MEMORY`                               | [`MemoryMapEntryKind::ACPI_RECLAIMABLE`] |
+    /// | `ACPI_MEMORY_NVS`                                   | [`MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE`] |
+    /// | `UNUSABLE_MEMORY`                                   | [`MemoryMapEntryKind::UNUSABLE`]        |
+    /// | `UNACCEPTED_MEMORY_TYPE`                            | [`MemoryMapEntryKind::UNACCEPTED`]      |
+    /// | anything else                                       | [`MemoryMapEntryKind::RESERVED`]        |
+    ///
+    /// Regions with the [`UefiMemoryDescriptor::MEMORY_RUNTIME`] attribute are always
+    /// [`MemoryMapEntryKind::RESERVED`].
+    pub const fn native_kind(&self) -> MemoryMapEntryKind {
+        if self.attribute & Self::MEMORY_RUNTIME != 0 {
+            return MemoryMapEntryKind::RESERVED;
+        }
+
+        match self.kind {
+            Self::CONVENTIONAL_MEMORY | Self::BOOT_SERVICES_CODE | Self::BOOT_SERVICES_DATA => {
+                MemoryMapEntryKind::USABLE
+            }
+            Self::LOADER_CODE | Self::LOADER_DATA => MemoryMapEntryKind::BOOTLOADER,
+            Self::ACPI_RECLAIM_MEMORY => MemoryMapEntryKind::ACPI_RECLAIMABLE,
+            Self::ACPI_MEMORY_NVS => MemoryMapEntryKind::ACPI_NONVOLATILE_STORAGE,
+            Self::UNUSABLE_MEMORY => MemoryMapEntryKind::UNUSABLE,
+            Self::UNACCEPTED_MEMORY_TYPE => MemoryMapEntryKind::UNACCEPTED,
+            _ => MemoryMapEntryKind::RESERVED,
+        }
+    }
+
+    /// Returns `true` if the region must be preserved by the operating system, either because
+    /// the firmware uses it or because it cannot be used as ordinary memory.
+    pub const fn must_preserve(&self) -> bool {
+        self.attribute & Self::MEMORY_RUNTIME != 0
+            || matches!(
+                self.kind,
+                Self::RESERVED_MEMORY_TYPE
+                    | Self::RUNTIME_SERVICES_CODE
+                    | Self::RUNTIME_SERVICES_DATA
+                    | Self::UNUSABLE_MEMORY
+                    | Self::ACPI_MEMORY_NVS
+                    | Self::MEMORY_MAPPED_IO
+                    | Self::MEMORY_MAPPED_IO_PORT_SPACE
+                    | Self::PAL_CODE
+            )
+    }
+}
+
+/// An iterator over the [`UefiMemoryDescriptor`]s of a UEFI memory map.
+#[derive(Clone, Debug)]
+pub struct UefiMemoryDescriptors<'a> {
+    map: &'a [u8],
+    descriptor_size: usize,
+}
+
+impl<'a> UefiMemoryDescriptors<'a> {
+    /// Creates a new [`UefiMemoryDescriptors`] over `map`, in which each descriptor takes up
+    /// `descriptor_size` bytes.
+    ///
+    /// Returns [`None`] if `version` is not supported or `descriptor_size` is too small to
+    /// hold a [`UefiMemoryDescriptor`].
+    pub fn new(
+        map: &'a [u8],
+        descriptor_size: usize,
+        version: UefiDescriptorVersion,
+    ) -> Option<Self> {
+        if !version.is_supported() || descriptor_size < core::mem::size_of::<UefiMemoryDescriptor>()
+        {
+            return None;
+        }
+
+        Some(Self {
+            map,
+            descriptor_size,
+        })
+    }
+}
+
+impl Iterator for UefiMemoryDescriptors<'_> {
+    type Item = UefiMemoryDescriptor;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.map.len() < self.descriptor_size {
+            return None;
+        }
+
+        let (descriptor, rest) = self.map.split_at(self.descriptor_size);
+        self.map = rest;
+
+        // SAFETY: `descriptor` is at least `size_of::<UefiMemoryDescriptor>()` bytes long, as
+        // checked in `UefiMemoryDescriptors::new()`, and every bit pattern is a valid
+        // `UefiMemoryDescriptor`.
+        Some(unsafe {
+            descriptor
+                .as_ptr()
+                .cast::<UefiMemoryDescriptor>()
+                .read_unaligned()
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.map.len() / self.descriptor_size;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for UefiMemoryDescriptors<'_> {}
+
+/// Various errors that can occur when reconciling the native memory map with the UEFI
+/// memory map.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReconcileError {
+    /// The UEFI memory map is missing or uses an unsupported descriptor version.
+    UnsupportedDescriptors,
+    /// The native memory map marks as usable a range that the UEFI memory map requires to be
+    /// preserved.
+    Conflict {
+        /// The base of the conflicting range.
+        base: u64,
+        /// The size, in bytes, of the conflicting range.
+        size: u64,
+        /// The UEFI memory type of the conflicting range.
+        uefi_type: u32,
+    },
+}
+
+impl core::fmt::Display for ReconcileError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnsupportedDescriptors => f.write_str("uefi memory map cannot be interpreted"),
+            Self::Conflict {
+                base,
+                size,
+                uefi_type,
+            } => write!(
+                f,
+                "usable range {base:#x}-{:#x} has uefi memory type {uefi_type}",
+                base.saturating_add(*size)
+            ),
+        }
+    }
+}
+
+impl BootloaderResponse {
+    /// Cross-checks the native memory map against the UEFI memory map, reporting the first
+    /// range the native memory map marks as [`MemoryMapEntryKind::USABLE`] that the UEFI
+    /// memory map requires to be preserved.
+    ///
+    /// This is a diagnostic for catching bugs in the translation of the UEFI memory map.
+    ///
+    /// # Errors
+    /// - [`ReconcileError::UnsupportedDescriptors`]: the UEFI memory map cannot be
+    ///   interpreted.
+    /// - [`ReconcileError::Conflict`]: a usable range conflicts with the UEFI memory map.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::memory_map()`] and
+    /// [`BootloaderResponse::uefi_memory_map_descriptors()`] apply.
+    pub unsafe fn reconcile_uefi_map(&self) -> Result<(), ReconcileError> {
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::memory_map()`.
+        let memory_map = unsafe { self.memory_map() };
+        // SAFETY: the caller upholds the requirements of
+        // `BootloaderResponse::uefi_memory_map_descriptors()`.
+        let descriptors = unsafe { self.uefi_memory_map_descriptors() }
+            .ok_or(ReconcileError::UnsupportedDescriptors)?;
+
+        for descriptor in descriptors.filter(UefiMemoryDescriptor::must_preserve) {
+            for entry in memory_map
+                .iter()
+                .filter(|entry| entry.kind == MemoryMapEntryKind::USABLE)
+            {
+                let base = entry.base.max(descriptor.physical_start);
+                let end = entry.end_addr().as_u64().min(descriptor.physical_end());
+                if base < end {
+                    return Err(ReconcileError::Conflict {
+                        base,
+                        size: end - base,
+                        uefi_type: descriptor.kind,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Various errors that can occur when converting the UEFI memory map into
+/// [`MemoryMapEntry`]s.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConvertError {
+    /// The UEFI memory map is missing or uses an unsupported descriptor version.
+    UnsupportedDescriptors,
+    /// The output buffer has no room for the descriptor at `index`.
+    BufferTooSmall {
+        /// The index of the offending descriptor.
+        index: usize,
+    },
+}
+
+impl core::fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnsupportedDescriptors => f.write_str("uefi memory map cannot be interpreted"),
+            Self::BufferTooSmall { index } => {
+                write!(
+                    f,
+                    "no room in the output buffer for uefi descriptor {index}"
+                )
+            }
+        }
+    }
+}
+
+impl BootloaderResponse {
+    /// Converts each descriptor of the UEFI memory map into a [`MemoryMapEntry`] with the kind
+    /// given by [`UefiMemoryDescriptor::native_kind()`], writing them into `out` in order and
+    /// returning the number of entries written.
+    ///
+    /// # Errors
+    /// - [`ConvertError::UnsupportedDescriptors`]: the UEFI memory map cannot be interpreted.
+    /// - [`ConvertError::BufferTooSmall`]: `out` cannot hold every descriptor.
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::uefi_memory_map_descriptors()`] apply.
+    pub unsafe fn uefi_map_as_native(
+        &self,
+        out: &mut [MemoryMapEntry],
+    ) -> Result<usize, ConvertError> {
+        // SAFETY: the caller upholds the requirements of
+        // `BootloaderResponse::uefi_memory_map_descriptors()`.
+        let descriptors = unsafe { self.uefi_memory_map_descriptors() }
+            .ok_or(ConvertError::UnsupportedDescriptors)?;
+
+        let mut count = 0;
+        for (index, descriptor) in descriptors.enumerate() {
+            let slot = out
+                .get_mut(index)
+                .ok_or(ConvertError::BufferTooSmall { index })?;
+            *slot = MemoryMapEntry {
+                kind: descriptor.native_kind(),
+                base: descriptor.physical_start,
+                size: descriptor.physical_end() - descriptor.physical_start,
+            };
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{entry, response, set_memory_map};
+
+    /// Returns the bytes of `descriptors`, as laid out in a UEFI memory map.
+    fn map_bytes(descriptors: &[UefiMemoryDescriptor]) -> &[u8] {
+        // SAFETY: `UefiMemoryDescriptor` is plain old data, so its bytes may be read.
+        unsafe {
+            core::slice::from_raw_parts(
+                descriptors.as_ptr().cast::<u8>(),
+                core::mem::size_of_val(descriptors),
+            )
+        }
+    }
+
+    fn descriptor(kind: u32, physical_start: u64, number_of_pages: u64) -> UefiMemoryDescriptor {
+        UefiMemoryDescriptor {
+            kind,
+            physical_start,
+            virtual_start: 0,
+            number_of_pages,
+            attribute: 0,
+        }
+    }
+
+    #[test]
+    fn unsupported_version_produces_no_descriptors() {
+        let descriptors = [descriptor(UefiMemoryDescriptor::CONVENTIONAL_MEMORY, 0, 1)];
+        let map = map_bytes(&descriptors);
+        let size = core::mem::size_of::<UefiMemoryDescriptor>();
+
+        assert!(UefiMemoryDescriptors::new(map, size, UefiDescriptorVersion(2)).is_none());
+        assert_eq!(
+            UefiMemoryDescriptors::new(map, size, UefiDescriptorVersion::V1).map(Iterator::count),
+            Some(1)
+        );
+
+        let mut response = response();
+        response.uefi_memory_map = map.as_ptr().cast();
+        response.uefi_memory_map_size = map.len();
+        response.uefi_memory_map_descriptor_size = size;
+        response.uefi_memory_map_descriptor_version = 0;
+        // SAFETY: the UEFI memory map points to `map`, which outlives `response`.
+        assert!(unsafe { response.uefi_memory_map_descriptors() }.is_none());
+    }
+
+    #[test]
+    fn reconcile_reports_usable_runtime_services() {
+        let descriptors = [
+            descriptor(UefiMemoryDescriptor::CONVENTIONAL_MEMORY, 0, 8),
+            descriptor(UefiMemoryDescriptor::RUNTIME_SERVICES_DATA, 0x8000, 2),
+        ];
+        let map = map_bytes(&descriptors);
+        let mut entries = [entry(MemoryMapEntryKind::USABLE, 0, 0x10000)];
+        let mut response = response();
+        response.uefi_memory_map = map.as_ptr().cast();
+        response.uefi_memory_map_size = map.len();
+        response.uefi_memory_map_descriptor_size = core::mem::size_of::<UefiMemoryDescriptor>();
+        response.uefi_memory_map_descriptor_version = UefiDescriptorVersion::V1.0;
+        set_memory_map(&mut response, &mut entries);
+
+        // SAFETY: both memory maps point into locals that outlive `response`.
+        assert_eq!(
+            unsafe { response.reconcile_uefi_map() },
+            Err(ReconcileError::Conflict {
+                base: 0x8000,
+                size: 0x2000,
+                uefi_type: UefiMemoryDescriptor::RUNTIME_SERVICES_DATA,
+            })
+        );
+
+        let mut entries = [entry(MemoryMapEntryKind::USABLE, 0, 0x8000)];
+        set_memory_map(&mut response, &mut entries);
+        // SAFETY: both memory maps point into locals that outlive `response`.
+        assert_eq!(unsafe { response.reconcile_uefi_map() }, Ok(()));
+    }
+
+    #[test]
+    fn uefi_map_converts_mixed_types() {
+        let mut runtime = descriptor(UefiMemoryDescriptor::BOOT_SERVICES_DATA, 0x5000, 1);
+        runtime.attribute = UefiMemoryDescriptor::MEMORY_RUNTIME;
+        let descriptors = [
+            descriptor(UefiMemoryDescriptor::CONVENTIONAL_MEMORY, 0, 2),
+            descriptor(UefiMemoryDescriptor::LOADER_DATA, 0x2000, 1),
+            descriptor(UefiMemoryDescriptor::RUNTIME_SERVICES_CODE, 0x3000, 1),
+            descriptor(UefiMemoryDescriptor::ACPI_RECLAIM_MEMORY, 0x4000, 1),
+            runtime,
+        ];
+        let map = map_bytes(&descriptors);
+        let mut response = response();
+        response.uefi_memory_map = map.as_ptr().cast();
+        response.uefi_memory_map_size = map.len();
+        response.uefi_memory_map_descriptor_size = core::mem::size_of::<UefiMemoryDescriptor>();
+        response.uefi_memory_map_descriptor_version = UefiDescriptorVersion::V1.0;
+
+        let mut out = [entry(MemoryMapEntryKind::USABLE, 0, 0); 5];
+        // SAFETY: the UEFI memory map points to `map`, which outlives `response`.
+        assert_eq!(unsafe { response.uefi_map_as_native(&mut out) }, Ok(5));
+        assert_eq!(
+            out,
+            [
+                entry(MemoryMapEntryKind::USABLE, 0, 0x2000),
+                entry(MemoryMapEntryKind::BOOTLOADER, 0x2000, 0x1000),
+                entry(MemoryMapEntryKind::RESERVED, 0x3000, 0x1000),
+                entry(MemoryMapEntryKind::ACPI_RECLAIMABLE, 0x4000, 0x1000),
+                entry(MemoryMapEntryKind::RESERVED, 0x5000, 0x1000),
+            ]
+        );
+
+        // SAFETY: the UEFI memory map points to `map`, which outlives `response`.
+        assert_eq!(
+            unsafe { response.uefi_map_as_native(&mut out[..4]) },
+            Err(ConvertError::BufferTooSmall { index: 4 })
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! Integer helpers shared by the memory map helpers.
+
+/// Returns `value` rounded up to the nearest multiple of `align`, saturating at [`u64::MAX`].
+///
+/// `align` must be a power of two.
+pub const fn align_up(value: u64, align: u64) -> u64 {
+    debug_assert!(align.is_power_of_two());
+
+    match value.checked_add(align - 1) {
+        Some(value) => align_down(value, align),
+        None => u64::MAX,
+    }
+}
+
+/// Returns `value` rounded down to the nearest multiple of `align`.
+///
+/// `align` must be a power of two.
+pub const fn align_down(value: u64, align: u64) -> u64 {
+    debug_assert!(align.is_power_of_two());
+
+    value & !(align - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligned_values_are_unchanged() {
+        assert_eq!(align_up(0, 0x1000), 0);
+        assert_eq!(align_up(0x2000, 0x1000), 0x2000);
+        assert_eq!(align_down(0x2000, 0x1000), 0x2000);
+        assert_eq!(align_up(7, 1), 7);
+    }
+
+    #[test]
+    fn mid_range_values_round() {
+        assert_eq!(align_up(0x1001, 0x1000), 0x2000);
+        assert_eq!(align_up(0x1fff, 0x1000), 0x2000);
+        assert_eq!(align_down(0x1fff, 0x1000), 0x1000);
+        assert_eq!(align_down(0x20_1234, 2 << 20), 0x20_0000);
+    }
+
+    #[test]
+    fn align_up_saturates_at_top() {
+        assert_eq!(align_up(u64::MAX, 0x1000), u64::MAX);
+        assert_eq!(align_up(u64::MAX - 0xffe, 0x1000), u64::MAX);
+        assert_eq!(align_up(u64::MAX - 0x1ffe, 0x1000), u64::MAX - 0xfff);
+        assert_eq!(align_down(u64::MAX, 0x1000), u64::MAX - 0xfff);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn non_power_of_two_align_panics() {
+        align_up(0x1000, 0x1800);
+    }
+}
@@ -0,0 +1,328 @@
+//! A safe view of a [`BootloaderResponse`] whose contents have been vouched for.
+
+use core::str::Utf8Error;
+
+use crate::{
+    is_supported_version, BootloaderResponse, MemoryMapEntry, ModuleEntry, RESPONSE_SIGNATURE,
+};
+
+/// Various errors that can occur when obtaining a [`ResponseView`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResponseError {
+    /// The pointer to the [`BootloaderResponse`] is NULL.
+    Null,
+    /// The pointer to the [`BootloaderResponse`] is not properly aligned.
+    Misaligned,
+    /// The [`BootloaderResponse`] does not start with [`RESPONSE_SIGNATURE`].
+    InvalidSignature,
+    /// The [`BootloaderResponse`] was constructed using an API version that is older than
+    /// expected or not supported.
+    UnsupportedVersion,
+    /// The [`BootloaderResponse`] failed its [`audit`](BootloaderResponse::audit).
+    Inconsistent,
+}
+
+impl core::fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Null => f.write_str("bootloader response pointer is NULL"),
+            Self::Misaligned => f.write_str("bootloader response pointer is misaligned"),
+            Self::InvalidSignature => f.write_str("bootloader response has an invalid signature"),
+            Self::UnsupportedVersion => {
+                f.write_str("bootloader response has an unsupported api version")
+            }
+            Self::Inconsistent => f.write_str("bootloader response failed its audit"),
+        }
+    }
+}
+
+/// A [`BootloaderResponse`] whose pointers are known to be valid for `'a`.
+///
+/// Since its pointers have been vouched for upon construction, the accessors of a
+/// [`ResponseView`] are safe.
+#[derive(Clone, Copy, Debug)]
+pub struct ResponseView<'a> {
+    response: &'a BootloaderResponse,
+}
+
+impl ResponseView<'static> {
+    /// Creates a [`ResponseView`] from the value of the register through which the bootloader
+    /// passed the [`BootloaderResponse`] upon kernel entry.
+    ///
+    /// This is intended to be the first call the kernel entry point makes.
+    ///
+    /// # Errors
+    /// - [`ResponseError::Null`]: `reg` is 0.
+    /// - [`ResponseError::Misaligned`]: `reg` is not aligned to a [`BootloaderResponse`].
+    /// - [`ResponseError::InvalidSignature`]: the response does not start with
+    ///   [`RESPONSE_SIGNATURE`].
+    ///
+    /// # Safety
+    /// If `reg` is a non-zero, aligned value, it must point to a [`BootloaderResponse`] that
+    /// upholds the requirements of [`ResponseView::new`]. The `'static` lifetime is an
+    /// assertion by the caller that the memory containing the response is never reclaimed
+    /// while the [`ResponseView`] or anything derived from it is in use.
+    pub unsafe fn from_register(reg: usize) -> Result<Self, ResponseError> {
+        let ptr = reg as *const BootloaderResponse;
+        if ptr.is_null() {
+            return Err(ResponseError::Null);
+        }
+        if !ptr.is_aligned() {
+            return Err(ResponseError::Misaligned);
+        }
+
+        // SAFETY: the caller guarantees that `ptr` points to a valid `BootloaderResponse` for
+        // `'static`.
+        let response = unsafe { &*ptr };
+        if response.signature != RESPONSE_SIGNATURE {
+            return Err(ResponseError::InvalidSignature);
+        }
+
+        // SAFETY: the caller guarantees that `response` upholds the requirements of
+        // `ResponseView::new()`.
+        Ok(unsafe { Self::new(response) })
+    }
+}
+
+impl<'a> ResponseView<'a> {
+    /// Creates a [`ResponseView`] of `response`.
+    ///
+    /// # Safety
+    /// Every pointer in `response` must uphold the requirements of the corresponding
+    /// [`BootloaderResponse`] accessor for the lifetime `'a`.
+    pub const unsafe fn new(response: &'a BootloaderResponse) -> Self {
+        Self { response }
+    }
+
+    /// Creates a [`ResponseView`] of the [`BootloaderResponse`] to which `ptr` points, valid
+    /// for the caller-chosen lifetime `'a`.
+    ///
+    /// Every slice and string obtained from the [`ResponseView`] borrows for `'a`, so memory
+    /// backing the response can only be reclaimed once those borrows have ended.
+    ///
+    /// # Safety
+    /// `ptr` must point to a [`BootloaderResponse`] that remains valid for `'a` and upholds
+    /// the requirements of [`ResponseView::new`].
+    pub unsafe fn assume_valid_for(ptr: *const BootloaderResponse) -> Self {
+        // SAFETY: the caller guarantees that `ptr` points to a valid `BootloaderResponse` for
+        // `'a` that upholds the requirements of `ResponseView::new()`.
+        unsafe { Self::new(&*ptr) }
+    }
+
+    /// Returns the underlying [`BootloaderResponse`].
+    pub const fn response(&self) -> &'a BootloaderResponse {
+        self.response
+    }
+
+    /// Returns the [`MemoryMapEntry`]s provided by the bootloader.
+    pub fn memory_map(&self) -> &'a [MemoryMapEntry] {
+        // SAFETY: the creator of `self` vouched for the memory map.
+        unsafe { self.response.memory_map() }
+    }
+
+    /// Returns the [`ModuleEntry`]s provided by the bootloader.
+    pub fn modules(&self) -> &'a [ModuleEntry] {
+        // SAFETY: the creator of `self` vouched for the modules.
+        unsafe { self.response.modules() }
+    }
+
+    /// Returns the name of the loading bootloader.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the name is not valid utf-8.
+    pub fn bootloader_name(&self) -> Result<&'a str, Utf8Error> {
+        // SAFETY: the creator of `self` vouched for the bootloader name.
+        unsafe { self.response.bootloader_name() }
+    }
+
+    /// Returns the version of the loading bootloader.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the version is not valid utf-8.
+    pub fn bootloader_version(&self) -> Result<&'a str, Utf8Error> {
+        // SAFETY: the creator of `self` vouched for the bootloader version.
+        unsafe { self.response.bootloader_version() }
+    }
+
+    /// Returns the [`PROTOCOL_REVISION`] the loading bootloader was built against.
+    ///
+    /// # Errors
+    /// Returns [`Utf8Error`] if the revision is not valid utf-8.
+    ///
+    /// [`PROTOCOL_REVISION`]: crate::PROTOCOL_REVISION
+    pub fn protocol_revision(&self) -> Result<&'a str, Utf8Error> {
+        // SAFETY: the creator of `self` vouched for the protocol revision.
+        unsafe { self.response.protocol_revision() }
+    }
+}
+
+impl core::ops::Deref for ResponseView<'_> {
+    type Target = BootloaderResponse;
+
+    fn deref(&self) -> &Self::Target {
+        self.response
+    }
+}
+
+impl BootloaderResponse {
+    /// Validates that this [`BootloaderResponse`] can be trusted by a kernel that requires at
+    /// least `expected_version` of the API.
+    ///
+    /// This is the single check a cautious kernel should run before using any field.
+    ///
+    /// # Errors
+    /// - [`ResponseError::InvalidSignature`]: the response does not start with
+    ///   [`RESPONSE_SIGNATURE`].
+    /// - [`ResponseError::UnsupportedVersion`]: the response's API version is older than
+    ///   `expected_version` or is not supported by this crate.
+    /// - [`ResponseError::Inconsistent`]: the response failed its
+    ///   [`audit`](BootloaderResponse::audit).
+    ///
+    /// # Safety
+    /// The same requirements as [`BootloaderResponse::audit()`] apply.
+    pub unsafe fn validate_for_kernel(&self, expected_version: u64) -> Result<(), ResponseError> {
+        if self.signature != RESPONSE_SIGNATURE {
+            return Err(ResponseError::InvalidSignature);
+        }
+        if self.api_version < expected_version || !is_supported_version(self.api_version) {
+            return Err(ResponseError::UnsupportedVersion);
+        }
+        // SAFETY: the caller upholds the requirements of `BootloaderResponse::audit()`.
+        if !unsafe { self.audit() }.passed() {
+            return Err(ResponseError::Inconsistent);
+        }
+
+        Ok(())
+    }
+}
+
+/// Panics if the bootloader did not load the kernel at the virtual address to which it was
+/// linked.
+///
+/// With one argument, the expected address is that of the `__kernel_start` symbol, which the
+/// kernel's linker script must define at the base of the kernel image. With two arguments,
+/// the expected address is given explicitly as a `usize`.
+///
+/// The first argument is anything that dereferences to a [`BootloaderResponse`], such as a
+/// [`ResponseView`].
+///
+/// # Examples
+/// ```
+/// use boot_api::BootloaderResponse;
+///
+/// // SAFETY: every field of `BootloaderResponse` is an integer or a raw pointer, for which all
+/// // zeroes is a valid value.
+/// let mut response: BootloaderResponse = unsafe { core::mem::zeroed() };
+/// response.kernel_virtual_address = 0xffff_ffff_8000_0000 as *const _;
+///
+/// // Expands to a comparison of `response.kernel_virtual_address()` against the address.
+/// boot_api::capora_assert_load_address!(response, 0xffff_ffff_8000_0000);
+/// ```
+///
+/// With one argument, the linker resolves `__kernel_start`, which is defined here by the
+/// example rather than by a linker script:
+///
+/// ```
+/// use boot_api::BootloaderResponse;
+///
+/// #[no_mangle]
+/// static __kernel_start: u8 = 0;
+///
+/// fn main() {
+///     // SAFETY: every field of `BootloaderResponse` is an integer or a raw pointer, for which
+///     // all zeroes is a valid value.
+///     let mut response: BootloaderResponse = unsafe { core::mem::zeroed() };
+///     response.kernel_virtual_address = (&raw const __kernel_start).cast();
+///
+///     boot_api::capora_assert_load_address!(response);
+/// }
+/// ```
+///
+/// A mismatch panics with a message naming both addresses, here `kernel loaded at
+/// 0xffffffff80200000 but linked at 0xffffffff80000000`:
+///
+/// ```should_panic
+/// # use boot_api::BootloaderResponse;
+/// # // SAFETY: all zeroes is a valid `BootloaderResponse`.
+/// # let mut response: BootloaderResponse = unsafe { core::mem::zeroed() };
+/// response.kernel_virtual_address = 0xffff_ffff_8020_0000 as *const _;
+///
+/// boot_api::capora_assert_load_address!(response, 0xffff_ffff_8000_0000);
+/// ```
+#[macro_export]
+macro_rules! capora_assert_load_address {
+    ($response:expr) => {{
+        unsafe extern "C" {
+            static __kernel_start: u8;
+        }
+
+        $crate::capora_assert_load_address!($response, &raw const __kernel_start as usize)
+    }};
+    ($response:expr, $expected:expr) => {{
+        let expected: usize = $expected;
+        let actual = $response.kernel_virtual_address().as_usize();
+        assert!(
+            actual == expected,
+            "kernel loaded at {actual:#x} but linked at {expected:#x}"
+        );
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::boxed::Box;
+
+    use super::*;
+    use crate::{test_util::response, API_VERSION, MIN_SUPPORTED_API_VERSION};
+
+    #[test]
+    fn from_register() {
+        let response: &'static BootloaderResponse = Box::leak(Box::new(response()));
+
+        // SAFETY: `response` is a valid `BootloaderResponse` that is never freed.
+        let view = unsafe { ResponseView::from_register(response as *const _ as usize) };
+        assert!(view.is_ok_and(|view| core::ptr::eq(view.response(), response)));
+
+        // SAFETY: a NULL register value is never dereferenced.
+        let view = unsafe { ResponseView::from_register(0) };
+        assert_eq!(view.err(), Some(ResponseError::Null));
+    }
+
+    #[test]
+    fn validate_for_kernel() {
+        let mut response = response();
+        // SAFETY: `response` has no arrays.
+        assert_eq!(unsafe { response.validate_for_kernel(API_VERSION) }, Ok(()));
+
+        // SAFETY: `response` has no arrays.
+        assert_eq!(
+            unsafe { response.validate_for_kernel(API_VERSION + 1) },
+            Err(ResponseError::UnsupportedVersion)
+        );
+
+        response.api_version = MIN_SUPPORTED_API_VERSION - 1;
+        // SAFETY: `response` has no arrays.
+        assert_eq!(
+            unsafe { response.validate_for_kernel(0) },
+            Err(ResponseError::UnsupportedVersion)
+        );
+
+        response.api_version = API_VERSION;
+        response.signature[0] ^= 1;
+        // SAFETY: `response` has no arrays.
+        assert_eq!(
+            unsafe { response.validate_for_kernel(API_VERSION) },
+            Err(ResponseError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "kernel loaded at 0xffffffff80200000 but linked at 0xffffffff80000000"
+    )]
+    fn load_address_mismatch_panics() {
+        let mut response = response();
+        response.kernel_virtual_address = 0xffff_ffff_8020_0000 as *const _;
+        crate::capora_assert_load_address!(response, 0xffff_ffff_8000_0000);
+    }
+}
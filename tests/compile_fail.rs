@@ -0,0 +1,8 @@
+//! Checks that misuse of the crate's compile-time guarantees is rejected.
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
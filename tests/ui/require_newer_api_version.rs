@@ -0,0 +1,3 @@
+boot_api::require_api_version!(1000);
+
+fn main() {}
@@ -0,0 +1,16 @@
+use boot_api::{view::ResponseView, BootloaderResponse};
+
+fn main() {
+    // SAFETY: every field of `BootloaderResponse` is an integer or a raw pointer, for which all
+    // zeroes is a valid value.
+    let mut response: BootloaderResponse = unsafe { core::mem::zeroed() };
+    // SAFETY: the memory map and modules of `response` are empty.
+    let view = unsafe { ResponseView::new(&response) };
+    let memory_map = view.memory_map();
+
+    // Reclaiming the memory backing the response must wait until `memory_map` is unused.
+    // SAFETY: all zeroes is a valid `BootloaderResponse`.
+    response = unsafe { core::mem::zeroed() };
+    let _ = memory_map.len();
+    let _ = response;
+}
@@ -0,0 +1,14 @@
+use boot_api::{view::ResponseView, BootloaderResponse, MemoryMapEntry};
+
+fn memory_map() -> &'static [MemoryMapEntry] {
+    // SAFETY: every field of `BootloaderResponse` is an integer or a raw pointer, for which all
+    // zeroes is a valid value.
+    let response: BootloaderResponse = unsafe { core::mem::zeroed() };
+    // SAFETY: the memory map and modules of `response` are empty.
+    let view = unsafe { ResponseView::new(&response) };
+    view.memory_map()
+}
+
+fn main() {
+    let _ = memory_map();
+}